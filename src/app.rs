@@ -5,20 +5,29 @@ use std::time::{Duration, Instant};
 use color_eyre::Result;
 use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
 use ratatui::{
-    layout::{Alignment, Constraint, Direction, Layout},
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Style},
     text::{Line, Text},
-    widgets::{canvas::{Canvas, Painter, Shape}, Block, Borders, Paragraph},
+    widgets::{canvas::{Canvas, Painter, Shape}, Block, Borders, LineGauge, List, ListItem, ListState, Paragraph},
     DefaultTerminal, Frame,
 };
 
-use crate::{intro, types::{Simulation, LED}};
+use crate::{
+    intro,
+    playlist::{Playlist, PlaylistEntry},
+    simulations::Strike,
+    transition::{Transition, TransitionStyle},
+    types::{Simulation, LED},
+};
 
 #[derive(Debug)]
 enum AppPage {
     Intro,
     Menu(usize),
-    Simulation(usize, Instant),
+    Simulation(usize),
+    /// Back to the menu was pressed, but the simulation is still playing its ending.
+    Stopping(usize),
+    Playlist,
 }
 
 #[derive(Debug)]
@@ -33,20 +42,169 @@ pub struct App {
     current_leds: Vec<LED>,
 
     current_intensity_mod: f32,
+
+    /// When the app started. Simulations are ticked with the microseconds since then.
+    start_time: Instant,
+
+    /// The transition currently running on the simulation page, if any.
+    transition: Option<Transition>,
+    /// Style used for the next transition.
+    transition_style: TransitionStyle,
+    /// How long the next transition takes.
+    transition_duration: Duration,
+
+    /// The playlist built from the menu or loaded from a file. May be empty.
+    playlist: Playlist,
+    /// The preset last picked from the simulation page for each simulation, if any.
+    presets: Vec<Option<usize>>,
+
+    /// The match struck to light the first flame shown after the app starts, like the board does when it powers on,
+    /// and the buffer the simulation underneath it draws into. None once it has handed off.
+    power_on: Option<(Strike, Vec<LED>)>,
+    /// Whether the power-on match has been struck yet.
+    powered_on: bool,
+
+    /// How far the menu is scrolled, so the highlighted simulation stays in view.
+    menu_state: ListState,
 }
 
 impl App {
     /// Construct a new instance of [`App`].
     #[must_use] pub fn new(simulations: Vec<Box<dyn Simulation>>, leds: Vec<LED>,) -> Self {
+        let presets = vec![None; simulations.len()];
         Self {
             running: false,
             page: AppPage::Intro,
             simulations,
             current_leds: leds,
             current_intensity_mod: 1.0,
+            start_time: Instant::now(),
+            transition: None,
+            transition_style: TransitionStyle::Crossfade,
+            transition_duration: Duration::from_secs(2),
+            playlist: Playlist::new(vec![]),
+            presets,
+            power_on: None,
+            powered_on: false,
+            menu_state: ListState::default(),
         }
     }
 
+    /// Start with a playlist already loaded, ready to be played from the menu.
+    #[must_use] pub fn with_playlist(mut self, playlist: Playlist) -> Self {
+        self.playlist = playlist;
+        self
+    }
+
+    /// Microseconds since the app started.
+    fn micros(&self) -> u64 {
+        self.start_time.elapsed().as_micros().try_into().unwrap()
+    }
+
+    /// Tick the running simulation (or both sides of the running transition) into `current_leds`.
+    fn tick_simulation(&mut self, simnum: usize) {
+        let micros = self.micros();
+        if let Some(transition) = &mut self.transition {
+            transition.tick(&mut self.simulations, &mut self.current_leds, micros, self.current_intensity_mod);
+            if transition.is_finished(micros) {
+                let transition = self.transition.take().unwrap();
+                self.page = AppPage::Simulation(transition.to);
+                self.current_leds = transition.into_leds();
+            }
+        } else {
+            let mut leds = self.take_frame();
+            self.simulations[simnum].tick(&mut leds, micros, self.current_intensity_mod);
+            self.show_frame(leds, micros);
+            if matches!(self.page, AppPage::Stopping(_)) && self.simulations[simnum].is_stopped() {
+                self.power_on = None; // don't carry a match that's still burning over to the next simulation
+                self.page = AppPage::Menu(0);
+            }
+        }
+    }
+
+    /// Strike the power-on match over simulation `simnum`, if it's the first flame lit since the app started.
+    /// Test patterns, and simulations that light themselves, are left alone and don't use up the match.
+    fn power_on(&mut self, simnum: usize, micros: u64) {
+        if !self.powered_on && self.simulations[simnum].lit_by_power_on() {
+            self.powered_on = true;
+            self.power_on = Some((Strike::new(&self.current_leds, micros), self.current_leds.clone()));
+        }
+    }
+
+    /// Take the buffer the running simulation draws into, for it to draw its next frame.
+    fn take_frame(&mut self) -> Vec<LED> {
+        match &mut self.power_on {
+            Some((_, buffer)) => std::mem::take(buffer),
+            None => std::mem::take(&mut self.current_leds),
+        }
+    }
+
+    /// Show the frame the running simulation just drew, with the power-on match over it while it's still burning.
+    fn show_frame(&mut self, leds: Vec<LED>, micros: u64) {
+        match &mut self.power_on {
+            Some((strike, buffer)) if !strike.is_done(micros) => {
+                self.current_leds.clone_from(&leds);
+                strike.draw(&mut self.current_leds, &leds, micros);
+                *buffer = leds;
+            }
+            _ => {
+                self.power_on = None;
+                self.current_leds = leds;
+            }
+        }
+    }
+
+    /// Start transitioning from the running simulation to the one `offset` places away in the list.
+    fn start_transition(&mut self, offset: isize) {
+        let AppPage::Simulation(simnum) = self.page else {
+            return;
+        };
+        if self.transition.is_some() {
+            return; // let the running transition finish first
+        }
+        let to = (simnum as isize + offset).rem_euclid(self.simulations.len() as isize) as usize;
+        if to == simnum {
+            return;
+        }
+        let micros = self.micros();
+        // the match stops at the transition, so start from the frame underneath it. simulations that don't
+        // redraw every LED would otherwise keep the match in their frame
+        let power_on = self.power_on.take();
+        let leds = power_on.as_ref().map_or(&self.current_leds, |(_, buffer)| buffer);
+        self.transition = Some(Transition::new(
+            &mut self.simulations,
+            simnum,
+            to,
+            self.transition_style,
+            self.transition_duration,
+            micros,
+            leds,
+        ));
+    }
+
+    /// Add the simulation highlighted in the menu to the playlist, with the current intensity and preset.
+    fn add_to_playlist(&mut self, simnum: usize) {
+        let default_duration = Duration::from_secs(30); // config: how long entries added from the menu last
+        let preset = self.presets[simnum].map(|preset| self.simulations[simnum].get_presets()[preset].to_owned());
+        self.playlist.push(PlaylistEntry {
+            simulation: simnum,
+            duration: default_duration,
+            intensity_mod: self.current_intensity_mod,
+            preset,
+        });
+    }
+
+    /// Switch the running simulation to its next preset.
+    fn next_preset(&mut self, simnum: usize) {
+        let presets = self.simulations[simnum].get_presets();
+        if presets.is_empty() {
+            return;
+        }
+        let preset = self.presets[simnum].map_or(0, |preset| (preset + 1) % presets.len());
+        self.simulations[simnum].apply_preset(presets[preset]);
+        self.presets[simnum] = Some(preset);
+    }
+
     /// Run the application's main loop.
     pub fn run(mut self, mut terminal: DefaultTerminal) -> Result<()> {
         self.running = true;
@@ -110,43 +268,60 @@ impl App {
                 frame.render_widget(instructions, chunks[2]);
             }
             AppPage::Menu(simnum) => {
+                let menu_layout = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints(
+                        [
+                            Constraint::Length(1),
+                            Constraint::Min(0),
+                            Constraint::Length(1),
+                        ]
+                        .as_ref(),
+                    )
+                    .split(chunks[1]);
+
                 let menu = Paragraph::new(Text::styled(
                     "Choose a simulation technique:",
                     Style::new().fg(Color::Green),
                 ));
-                frame.render_widget(menu, chunks[1]);
-
-                let mut simulation_lines = vec![];
-
-                for (i, simulation) in self.simulations.iter().enumerate() {
-                    let simulation_name = simulation.get_name();
-                    let simulation_line = Line::styled(
-                        simulation_name,
-                        Style::default().fg(if simnum == i {
-                            Color::Yellow
-                        } else {
-                            Color::White
-                        })
-                        .bg(if simnum == i {
-                            Color::Blue
-                        } else {
-                            Color::Reset
-                        }),
-                    );
-                    simulation_lines.push(simulation_line);
-                }
-                let simulation_paragraph = Paragraph::new(simulation_lines).centered();
-                frame.render_widget(simulation_paragraph, chunks[1]);
+                frame.render_widget(menu, menu_layout[0]);
+
+                // a list rather than a paragraph, so it scrolls to keep the highlighted simulation in view
+                let simulation_items: Vec<ListItem> = self
+                    .simulations
+                    .iter()
+                    .map(|simulation| ListItem::new(Line::raw(simulation.get_name()).centered()))
+                    .collect();
+                let simulation_list = List::new(simulation_items)
+                    .style(Style::new().fg(Color::White))
+                    .highlight_style(Style::new().fg(Color::Yellow).bg(Color::Blue));
+                self.menu_state.select(Some(simnum));
+                frame.render_stateful_widget(simulation_list, menu_layout[1], &mut self.menu_state);
+
+                let playlist = Paragraph::new(Line::styled(
+                    if self.playlist.is_empty() {
+                        "Playlist: empty".to_owned()
+                    } else {
+                        format!(
+                            "Playlist: {} entries, {:.0}s",
+                            self.playlist.entries().len(),
+                            self.playlist.total_duration().as_secs_f32(),
+                        )
+                    },
+                    Style::new().fg(Color::Green),
+                ))
+                .centered();
+                frame.render_widget(playlist, menu_layout[2]);
 
                 // status message
                 let status = Paragraph::new(
-                    Line::raw("Navigate: ↑/↓, Select: Enter, Quit: Esc/q")
+                    Line::raw("Navigate: ↑/↓, Select: Enter, Add to playlist: a, Play playlist: p, Clear playlist: c, Quit: Esc/q")
                         .style(Style::new().fg(Color::Yellow)),
                 )
                 .centered();
                 frame.render_widget(status, chunks[2]);
             }
-            AppPage::Simulation(simnum, start_time) => {
+            AppPage::Simulation(simnum) | AppPage::Stopping(simnum) => {
                 let simulation_layout = Layout::default()
                     .direction(Direction::Vertical)
                     .margin(1)
@@ -158,82 +333,98 @@ impl App {
                         .as_ref(),
                     )
                     .split(chunks[1]);
-                let simulation = &mut self.simulations[simnum];
-                
                 // tick the simulation
-                simulation.tick(&mut self.current_leds, start_time.elapsed().as_micros().try_into().unwrap(), self.current_intensity_mod);
-
-                // get bounding box of LEDs
-                let mut min_x = i32::MAX;
-                let mut max_x = i32::MIN;
-                let mut min_y = i32::MAX;
-                let mut max_y = i32::MIN;
-                for led in &self.current_leds {
-                    min_x = min_x.min(led.coords.0 as i32);
-                    max_x = max_x.max(led.coords.0 as i32);
-                    min_y = min_y.min(led.coords.1 as i32);
-                    max_y = max_y.max(led.coords.1 as i32);
-                }
-                // add some padding
-                min_x -= 3;
-                max_x += 3;
-                min_y -= 3;
-                max_y += 3;
-
-                let width = max_x - min_x;
-                let height = max_y - min_y;
-                let ideal_aspect_ratio = f64::from(width) / f64::from(height);
-
-                // now, the canvas has a fixed aspect ratio, so we need to adjust the aspect ratio of the bounding box by adding padding
-                // the canvas's size is (simulation_layout[0].width, simulation_layout[0].height * 2) because we have twice as much vertical resolution as horizontal
-                let canvas_aspect_ratio = f64::from(simulation_layout[0].width) / f64::from(simulation_layout[0].height * 2);
-                if canvas_aspect_ratio > ideal_aspect_ratio {
-                    // canvas is wider than the bounding box, so we need to add padding to the left 
-                    let new_width = (f64::from(height) * canvas_aspect_ratio) as i32;
-                    let padding = (new_width - width) / 2;
-                    min_x -= padding;
-                    max_x += padding;
-                } else {
-                    // canvas is taller than the bounding box, so we need to add padding to the top
-                    let new_height = (f64::from(width) / canvas_aspect_ratio) as i32;
-                    let padding = (new_height - height) / 2;
-                    min_y -= padding;
-                    max_y += padding;
-                }
-            
-
-                let canvas = Canvas::default()
-                    .block(Block::default().borders(Borders::ALL).title("Simulation: ".to_owned() + simulation.get_name()))
-                    .paint(|ctx| {
-                        self.current_leds.iter().map(|led| {
-                            let x = led.coords.0 as f64;
-                            let y = led.coords.1 as f64;
-                            let color = led.color;
-                            FilledCircle{
-                                x,
-                                y,
-                                radius: 2.0,
-                                color: Color::Rgb(color.r, color.g, color.b),
-                            }
-                        })
-                        .for_each(
-                            |circle| ctx.draw(&circle)
-                        );
-                    })
-                    .x_bounds([f64::from(min_x), f64::from(max_x)])
-                    .y_bounds([f64::from(min_y), f64::from(max_y)]);
-                frame.render_widget(canvas, simulation_layout[0]);
-                // current intensity
-                let intensity = Paragraph::new(
-                    Line::raw("Intensity: ".to_owned() + &format!("{:.1}", self.current_intensity_mod))
+                self.tick_simulation(simnum);
+                let title = match &self.transition {
+                    Some(transition) => format!(
+                        "Simulation: {} → {} ({})",
+                        self.simulations[transition.from].get_name(),
+                        self.simulations[transition.to].get_name(),
+                        transition.style().get_name(),
+                    ),
+                    None => match self.presets[simnum] {
+                        Some(preset) => format!(
+                            "Simulation: {} ({})",
+                            self.simulations[simnum].get_name(),
+                            self.simulations[simnum].get_presets()[preset],
+                        ),
+                        None => "Simulation: ".to_owned() + self.simulations[simnum].get_name(),
+                    },
+                };
+                self.draw_leds(frame, simulation_layout[0], title);
+
+                // current intensity and transition settings, plus anything the simulation wants to report
+                let mut info_lines = vec![
+                    Line::raw(format!(
+                        "Intensity: {:.1}, Transition: {} over {:.1}s",
+                        self.current_intensity_mod,
+                        self.transition_style.get_name(),
+                        self.transition_duration.as_secs_f32(),
+                    ))
                         .style(Style::new().fg(Color::Green)),
-                )
+                ];
+                if let Some(status) = self.simulations[simnum].get_status() {
+                    info_lines.push(Line::raw(status).style(Style::new().fg(Color::Green)));
+                }
+                let intensity = Paragraph::new(info_lines)
                 .centered();
                 frame.render_widget(intensity, simulation_layout[1]);
 
                 // status message
                 let status = Paragraph::new(
-                    Line::raw("Back to menu: Esc/q, Change intensity: ↑/↓")
+                    Line::raw("Back to menu: Esc/q, Change intensity: ↑/↓, Switch simulation: ←/→, Transition style: t, Transition length: [/], Next preset: p")
+                        .style(Style::new().fg(Color::Yellow)),
+                )
+                .centered();
+                frame.render_widget(status, chunks[2]);
+            }
+            AppPage::Playlist => {
+                let simulation_layout = Layout::default()
+                    .direction(Direction::Vertical)
+                    .margin(1)
+                    .constraints(
+                        [
+                            Constraint::Min(0),
+                            Constraint::Length(2),
+                        ]
+                        .as_ref(),
+                    )
+                    .split(chunks[1]);
+                let micros = self.micros();
+                let mut leds = self.take_frame();
+                self.playlist.tick(&mut self.simulations, &mut self.presets, &mut leds, micros);
+                self.show_frame(leds, micros);
+
+                let entry = self.playlist.current_entry();
+                let position = format!("Playlist {}/{}", self.playlist.current_index() + 1, self.playlist.entries().len());
+                let title = match self.playlist.transition() {
+                    Some(transition) => format!(
+                        "{position}: {} → {}",
+                        self.simulations[transition.from].get_name(),
+                        self.simulations[transition.to].get_name(),
+                    ),
+                    None => format!("{position}: {}", self.simulations[entry.simulation].get_name()),
+                };
+                self.draw_leds(frame, simulation_layout[0], title);
+
+                // progress through the current entry
+                let progress = self.playlist.progress(micros);
+                let gauge = LineGauge::default()
+                    .filled_style(Style::new().fg(Color::Green))
+                    .unfilled_style(Style::new().fg(Color::DarkGray))
+                    .label(format!(
+                        "{:.0}s / {:.0}s, Intensity: {:.1}{}",
+                        progress * entry.duration.as_secs_f32(),
+                        entry.duration.as_secs_f32(),
+                        entry.intensity_mod,
+                        entry.preset.as_ref().map_or(String::new(), |preset| format!(", Preset: {preset}")),
+                    ))
+                    .ratio(f64::from(progress));
+                frame.render_widget(gauge, simulation_layout[1]);
+
+                // status message
+                let status = Paragraph::new(
+                    Line::raw("Back to menu: Esc/q, Skip to next: →")
                         .style(Style::new().fg(Color::Yellow)),
                 )
                 .centered();
@@ -242,6 +433,69 @@ impl App {
         };
     }
 
+    /// Draws the LEDs where they'd be on the board, scaled to fit `area`.
+    fn draw_leds(&self, frame: &mut Frame, area: Rect, title: String) {
+        // get bounding box of LEDs
+        let mut min_x = i32::MAX;
+        let mut max_x = i32::MIN;
+        let mut min_y = i32::MAX;
+        let mut max_y = i32::MIN;
+        for led in &self.current_leds {
+            min_x = min_x.min(led.coords.0 as i32);
+            max_x = max_x.max(led.coords.0 as i32);
+            min_y = min_y.min(led.coords.1 as i32);
+            max_y = max_y.max(led.coords.1 as i32);
+        }
+        // add some padding
+        min_x -= 3;
+        max_x += 3;
+        min_y -= 3;
+        max_y += 3;
+
+        let width = max_x - min_x;
+        let height = max_y - min_y;
+        let ideal_aspect_ratio = f64::from(width) / f64::from(height);
+
+        // now, the canvas has a fixed aspect ratio, so we need to adjust the aspect ratio of the bounding box by adding padding
+        // the canvas's size is (area.width, area.height * 2) because we have twice as much vertical resolution as horizontal
+        let canvas_aspect_ratio = f64::from(area.width) / f64::from(area.height * 2);
+        if canvas_aspect_ratio > ideal_aspect_ratio {
+            // canvas is wider than the bounding box, so we need to add padding to the left 
+            let new_width = (f64::from(height) * canvas_aspect_ratio) as i32;
+            let padding = (new_width - width) / 2;
+            min_x -= padding;
+            max_x += padding;
+        } else {
+            // canvas is taller than the bounding box, so we need to add padding to the top
+            let new_height = (f64::from(width) / canvas_aspect_ratio) as i32;
+            let padding = (new_height - height) / 2;
+            min_y -= padding;
+            max_y += padding;
+        }
+
+        let canvas = Canvas::default()
+            .block(Block::default().borders(Borders::ALL).title(title))
+            .paint(|ctx| {
+                self.current_leds.iter().map(|led| {
+                    let x = led.coords.0 as f64;
+                    let y = led.coords.1 as f64;
+                    let color = led.color;
+                    FilledCircle{
+                        x,
+                        y,
+                        radius: 2.0,
+                        color: Color::Rgb(color.r, color.g, color.b),
+                    }
+                })
+                .for_each(
+                    |circle| ctx.draw(&circle)
+                );
+            })
+            .x_bounds([f64::from(min_x), f64::from(max_x)])
+            .y_bounds([f64::from(min_y), f64::from(max_y)]);
+        frame.render_widget(canvas, area);
+    }
+
     /// Reads the crossterm events and updates the state of [`App`].
     ///
     /// If your application needs to perform work in between handling events, you can use the
@@ -263,7 +517,17 @@ impl App {
         match (key.modifiers, key.code) {
             (_, KeyCode::Esc | KeyCode::Char('q')) => match self.page {
                 AppPage::Menu(_) => self.quit(),
-                AppPage::Simulation(..) => self.page = AppPage::Menu(0),
+                AppPage::Simulation(simnum) if self.transition.is_none() => {
+                    // let the simulation play its ending before going back
+                    let micros = self.micros();
+                    self.simulations[simnum].stop(micros);
+                    self.page = AppPage::Stopping(simnum);
+                }
+                AppPage::Simulation(..) | AppPage::Stopping(..) | AppPage::Playlist => {
+                    self.transition = None;
+                    self.power_on = None;
+                    self.page = AppPage::Menu(0);
+                }
                 AppPage::Intro => self.quit(),
             },
             (KeyModifiers::CONTROL, KeyCode::Char('c' | 'C')) => self.quit(),
@@ -279,7 +543,7 @@ impl App {
                         self.current_intensity_mod = 1.0;
                     }
                 }
-                AppPage::Intro => {}
+                AppPage::Intro | AppPage::Stopping(..) | AppPage::Playlist => {}
             },
             (_, KeyCode::Down) => match self.page {
                 AppPage::Menu(ref mut simnum) => {
@@ -293,17 +557,67 @@ impl App {
                         self.current_intensity_mod = 0.0;
                     }
                 }
-                AppPage::Intro => {}
+                AppPage::Intro | AppPage::Stopping(..) | AppPage::Playlist => {}
+            },
+            (_, KeyCode::Left) => self.start_transition(-1),
+            (_, KeyCode::Right) => {
+                if let AppPage::Playlist = self.page {
+                    let micros = self.micros();
+                    // skip ahead from the frame under the power-on match, like the playlist does when an entry ends
+                    let leds = self.power_on.as_ref().map_or(&self.current_leds, |(_, buffer)| buffer);
+                    self.playlist.advance(&mut self.simulations, &mut self.presets, leds, micros);
+                } else {
+                    self.start_transition(1);
+                }
+            }
+            (_, KeyCode::Char('a')) => {
+                if let AppPage::Menu(simnum) = self.page {
+                    self.add_to_playlist(simnum);
+                }
+            }
+            (_, KeyCode::Char('c')) => {
+                if let AppPage::Menu(_) = self.page {
+                    self.playlist.clear();
+                }
+            }
+            (_, KeyCode::Char('p')) => match self.page {
+                AppPage::Menu(_) if !self.playlist.is_empty() => {
+                    self.playlist.set_transition(self.transition_style, self.transition_duration);
+                    let micros = self.micros();
+                    self.playlist.start(&mut self.simulations, &mut self.presets, micros);
+                    self.power_on(self.playlist.current_entry().simulation, micros);
+                    self.page = AppPage::Playlist;
+                }
+                AppPage::Simulation(simnum) if self.transition.is_none() => self.next_preset(simnum),
+                _ => {}
             },
+            (_, KeyCode::Char('t')) => {
+                if let AppPage::Simulation(..) = self.page {
+                    self.transition_style = self.transition_style.next();
+                }
+            }
+            (_, KeyCode::Char('[')) => {
+                if let AppPage::Simulation(..) = self.page {
+                    self.transition_duration = self.transition_duration.saturating_sub(Duration::from_millis(500));
+                }
+            }
+            (_, KeyCode::Char(']')) => {
+                if let AppPage::Simulation(..) = self.page {
+                    self.transition_duration += Duration::from_millis(500);
+                }
+            }
             #[allow(clippy::single_match, reason = "the simulation page may care about Enter in the future")]
             (_, KeyCode::Enter) => match self.page {
                 AppPage::Menu(simnum) => {
-                    self.page = AppPage::Simulation(simnum, Instant::now());
+                    let micros = self.micros();
+                    self.simulations[simnum].start(micros);
+                    self.power_on(simnum, micros);
+                    self.page = AppPage::Simulation(simnum);
                 }
                 AppPage::Intro => {
                     self.page = AppPage::Menu(0);
                 }
-                AppPage::Simulation(..) => {}
+                AppPage::Simulation(..) | AppPage::Stopping(..) | AppPage::Playlist => {}
             },
             _ => {}
         }
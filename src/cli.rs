@@ -0,0 +1,139 @@
+//! Command-line argument parsing. The arguments are documented in [`crate::intro::TEXT`].
+use std::path::PathBuf;
+
+use color_eyre::eyre::{bail, eyre, Result, WrapErr};
+
+#[derive(Debug, PartialEq)]
+pub struct Options {
+    pub help: bool,
+    /// render frames to stdout instead of running the UI
+    pub headless: bool,
+    pub playlist: Option<PathBuf>,
+    /// a CSV of measured flame brightness to add as a simulation
+    pub recording: Option<PathBuf>,
+    /// a CSV of measured flame brightness to train a Markov model on and print, instead of running
+    pub train_markov: Option<PathBuf>,
+    /// name of the simulation to render when running headless without a playlist
+    pub simulation: Option<String>,
+    pub fps: u32,
+    /// how long to render for when running headless. Defaults to one pass through the playlist.
+    pub seconds: Option<f32>,
+    pub intensity_mod: f32,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            help: false,
+            headless: false,
+            playlist: None,
+            recording: None,
+            train_markov: None,
+            simulation: None,
+            fps: 30,
+            seconds: None,
+            intensity_mod: 1.0,
+        }
+    }
+}
+
+impl Options {
+    /// Parse the given arguments, not including the program name.
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self> {
+        let mut options = Self::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or_else(|| eyre!("{arg} needs a value"));
+            match arg.as_str() {
+                "-h" | "--help" => options.help = true,
+                "--headless" => options.headless = true,
+                "--playlist" => options.playlist = Some(PathBuf::from(value()?)),
+                "--recording" => options.recording = Some(PathBuf::from(value()?)),
+                "--train-markov" => options.train_markov = Some(PathBuf::from(value()?)),
+                "--simulation" => options.simulation = Some(value()?),
+                "--fps" => {
+                    options.fps = value()?.parse().wrap_err("--fps should be a whole number")?;
+                    if !(1..=1_000_000).contains(&options.fps) {
+                        bail!("--fps should be between 1 and 1000000, since frames are timed in whole microseconds");
+                    }
+                }
+                "--seconds" => {
+                    let seconds: f32 = value()?.parse().wrap_err("--seconds should be a number")?;
+                    if !seconds.is_finite() || seconds <= 0.0 {
+                        bail!("--seconds should be more than 0");
+                    }
+                    options.seconds = Some(seconds);
+                }
+                "--intensity" => {
+                    options.intensity_mod = value()?.parse().wrap_err("--intensity should be a number")?;
+                    if !(0.0..=1.0).contains(&options.intensity_mod) {
+                        bail!("--intensity should be between 0 and 1");
+                    }
+                }
+                _ => bail!("unknown argument {arg:?}, try --help"),
+            }
+        }
+        Ok(options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Options> {
+        Options::parse(args.iter().map(|arg| (*arg).to_owned()))
+    }
+
+    #[test]
+    fn parses_good_input() {
+        let options = parse(&["--headless", "--simulation", "Candle flame (colored)", "--seconds", "2.5", "--fps", "60", "--intensity", "0.5"]).unwrap();
+        assert_eq!(options, Options {
+            headless: true,
+            simulation: Some("Candle flame (colored)".to_owned()),
+            seconds: Some(2.5),
+            fps: 60,
+            intensity_mod: 0.5,
+            ..Options::default()
+        });
+    }
+
+    #[test]
+    fn no_arguments_is_the_default() {
+        assert_eq!(parse(&[]).unwrap(), Options::default());
+    }
+
+    #[test]
+    fn rejects_a_missing_value() {
+        assert!(parse(&["--playlist"]).is_err());
+        assert!(parse(&["--headless", "--seconds"]).is_err());
+    }
+
+    #[test]
+    fn rejects_a_bad_intensity() {
+        assert!(parse(&["--intensity", "bright"]).is_err());
+        assert!(parse(&["--intensity", "1.5"]).is_err());
+        assert!(parse(&["--intensity", "-0.1"]).is_err());
+    }
+
+    #[test]
+    fn rejects_a_bad_fps() {
+        assert!(parse(&["--fps", "0"]).is_err());
+        assert!(parse(&["--fps", "2000000"]).is_err());
+        assert!(parse(&["--fps", "29.97"]).is_err());
+    }
+
+    #[test]
+    fn rejects_bad_seconds() {
+        assert!(parse(&["--seconds", "soon"]).is_err());
+        assert!(parse(&["--seconds", "0"]).is_err());
+        assert!(parse(&["--seconds", "-1"]).is_err());
+        assert!(parse(&["--seconds", "NaN"]).is_err());
+        assert!(parse(&["--seconds", "inf"]).is_err());
+    }
+
+    #[test]
+    fn rejects_an_unknown_argument() {
+        assert!(parse(&["--verbose"]).is_err());
+    }
+}
@@ -0,0 +1,77 @@
+//! Color helpers shared between simulations.
+use crate::types::RGB;
+
+/// Convert HSV to RGB. `h` is in degrees from 0 to 360, `s` and `v` are from 0 to 1.
+#[must_use] pub fn hsv_to_rgb(h: f32, s: f32, v: f32) -> RGB<u8> {
+    let h = h.rem_euclid(360.0);
+    let c = v * s;
+    let x = c * (1.0 - ((h / 60.0) % 2.0 - 1.0).abs());
+    let m = v - c;
+
+    let (r, g, b) = if h < 60.0 {
+        (c, x, 0.0)
+    } else if h < 120.0 {
+        (x, c, 0.0)
+    } else if h < 180.0 {
+        (0.0, c, x)
+    } else if h < 240.0 {
+        (0.0, x, c)
+    } else if h < 300.0 {
+        (x, 0.0, c)
+    } else {
+        (c, 0.0, x)
+    };
+
+    RGB {
+        r: ((r + m) * 255.0).round() as u8,
+        g: ((g + m) * 255.0).round() as u8,
+        b: ((b + m) * 255.0).round() as u8,
+    }
+}
+
+/// Convert RGB to HSV, returned as `[h, s, v]` with the same ranges `hsv_to_rgb` takes.
+#[must_use] pub fn rgb_to_hsv(color: RGB<u8>) -> [f32; 3] {
+    let r = f32::from(color.r) / 255.0;
+    let g = f32::from(color.g) / 255.0;
+    let b = f32::from(color.b) / 255.0;
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let delta = max - min;
+
+    let hue = if delta == 0.0 {
+        0.0
+    } else if max == r {
+        60.0 * ((g - b) / delta).rem_euclid(6.0)
+    } else if max == g {
+        60.0 * ((b - r) / delta + 2.0)
+    } else {
+        60.0 * ((r - g) / delta + 4.0)
+    };
+    let sat = if max == 0.0 { 0.0 } else { delta / max };
+    [hue, sat, max]
+}
+
+/// Scale a color's brightness by `amount`, from 0 (black) to 1 (unchanged).
+#[must_use] pub fn scale(color: RGB<u8>, amount: f32) -> RGB<u8> {
+    let amount = amount.clamp(0.0, 1.0);
+    RGB {
+        r: (f32::from(color.r) * amount).round() as u8,
+        g: (f32::from(color.g) * amount).round() as u8,
+        b: (f32::from(color.b) * amount).round() as u8,
+    }
+}
+
+/// Blend from `a` to `b`, where `amount` is 0 for all `a` and 1 for all `b`.
+#[must_use] pub fn mix(a: RGB<u8>, b: RGB<u8>, amount: f32) -> RGB<u8> {
+    let lerp = |a: u8, b: u8| (f32::from(a) + (f32::from(b) - f32::from(a)) * amount).round() as u8;
+    RGB { r: lerp(a.r, b.r), g: lerp(a.g, b.g), b: lerp(a.b, b.b) }
+}
+
+/// Map heat from 0 to 1 onto flame colors: dark red, orange, yellow, then nearly white.
+#[must_use] pub fn heat_color(heat: f32) -> RGB<u8> {
+    let heat = heat.clamp(0.0, 1.0);
+    let hue = 50.0 * heat; // red to yellow
+    let sat = 1.0 - ((heat - 0.75) * 2.0).max(0.0); // wash out to white at the very hottest
+    let val = (heat * 1.6).min(1.0);
+    hsv_to_rgb(hue, sat, val)
+}
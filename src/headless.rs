@@ -0,0 +1,61 @@
+//! Headless rendering: run a simulation or playlist without the UI and print every frame to stdout.
+//!
+//! Each frame is one line: the time in microseconds followed by the color of every LED as `#rrggbb`,
+//! in the same order as the layout. Time is simulated rather than real, so rendering is as fast as the machine allows.
+use std::io::{BufWriter, Write};
+
+use color_eyre::eyre::{bail, eyre, Result};
+
+use crate::{
+    cli::Options,
+    playlist::Playlist,
+    types::{Simulation, LED},
+};
+
+pub fn run(
+    mut simulations: Vec<Box<dyn Simulation>>,
+    mut leds: Vec<LED>,
+    mut playlist: Option<Playlist>,
+    options: &Options,
+) -> Result<()> {
+    let simulation = match (&playlist, &options.simulation) {
+        (Some(_), None) => None,
+        (None, Some(name)) => Some(
+            simulations
+                .iter()
+                .position(|simulation| simulation.get_name() == name)
+                .ok_or_else(|| eyre!("no simulation named {name:?}"))?,
+        ),
+        _ => bail!("running headless needs exactly one of --playlist or --simulation"),
+    };
+    let seconds = match (options.seconds, &playlist) {
+        (Some(seconds), _) => seconds,
+        (None, Some(playlist)) => playlist.total_duration().as_secs_f32(),
+        (None, None) => bail!("running a single simulation headless needs --seconds"),
+    };
+
+    let frames = (seconds * options.fps as f32).ceil() as u64;
+    let mut out = BufWriter::new(std::io::stdout().lock());
+    let mut presets = vec![None; simulations.len()];
+
+    match (&mut playlist, simulation) {
+        (Some(playlist), _) => playlist.start(&mut simulations, &mut presets, 0),
+        (None, Some(simnum)) => simulations[simnum].start(0),
+        (None, None) => unreachable!(),
+    }
+    for frame in 0..frames {
+        let micros = frame * 1_000_000 / u64::from(options.fps); // rather than adding up a rounded frame length, which drifts
+        match (&mut playlist, simulation) {
+            (Some(playlist), _) => playlist.tick(&mut simulations, &mut presets, &mut leds, micros),
+            (None, Some(simnum)) => simulations[simnum].tick(&mut leds, micros, options.intensity_mod),
+            (None, None) => unreachable!(),
+        }
+        write!(out, "{micros}")?;
+        for led in &leds {
+            write!(out, " #{:02x}{:02x}{:02x}", led.color.r, led.color.g, led.color.b)?;
+        }
+        writeln!(out)?;
+    }
+    out.flush()?;
+    Ok(())
+}
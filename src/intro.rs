@@ -11,10 +11,22 @@ NeoPixels are sized and located as they will be on the PCB, so it's an accurate
 simulation.
 
 Available controls will be displayed at the bottom of the screen. Ctrl-C will
-always exit the program.
+always exit the program. Like the board powering on, the first flame you open
+is lit by striking a match.
 
 Command-line arguments:
 -h, --help: print this help message
+--playlist <file>: load a playlist (one `name | seconds | intensity | preset`
+                   entry per line, intensity and preset optional)
+--recording <file>: add a simulation that plays back a CSV of measured
+                    brightness (`seconds, value, ...` per line), and one
+                    that flickers like it using a Markov chain
+--train-markov <file>: train a Markov chain on a recording and print its
+                       tables as Rust constants for the board
+--headless: print frames to stdout instead of showing the UI. Needs
+            --playlist or --simulation <name>
+--fps <n>, --seconds <n>, --intensity <0-1>: headless frame rate, length
+                                             and single-simulation intensity
 
 To write your own simulation, see the README for instructions. It's available
 at https://github.com/rivques/flame_decoration_simulator."#;
\ No newline at end of file
@@ -0,0 +1,102 @@
+//! Helpers for reasoning about where the LEDs are, so simulations don't have to hard-code the board.
+use crate::types::{Role, LED, RGB};
+
+/// The bounding box of a set of LEDs.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Bounds {
+    pub min_x: f32,
+    pub max_x: f32,
+    pub min_y: f32,
+    pub max_y: f32,
+}
+
+impl Bounds {
+    #[must_use] pub fn of(leds: &[LED]) -> Self {
+        let mut bounds = Self { min_x: f32::MAX, max_x: f32::MIN, min_y: f32::MAX, max_y: f32::MIN };
+        for led in leds {
+            bounds.min_x = bounds.min_x.min(led.coords.0 as f32);
+            bounds.max_x = bounds.max_x.max(led.coords.0 as f32);
+            bounds.min_y = bounds.min_y.min(led.coords.1 as f32);
+            bounds.max_y = bounds.max_y.max(led.coords.1 as f32);
+        }
+        bounds
+    }
+
+    #[must_use] pub fn width(&self) -> f32 {
+        self.max_x - self.min_x
+    }
+
+    #[must_use] pub fn height(&self) -> f32 {
+        self.max_y - self.min_y
+    }
+
+    #[must_use] pub fn center(&self) -> (f32, f32) {
+        ((self.min_x + self.max_x) / 2.0, (self.min_y + self.max_y) / 2.0)
+    }
+}
+
+/// A copy of `leds` with every LED turned off, for simulations that need a buffer of their own to draw into.
+#[must_use] pub fn blank_leds(leds: &[LED]) -> Vec<LED> {
+    leds.iter().map(|led| LED { color: RGB { r: 0, g: 0, b: 0 }, ..led.clone() }).collect()
+}
+
+/// The indices of the LEDs the layout tags with `role`. Empty if the layout doesn't tag any.
+#[must_use] pub fn with_role(leds: &[LED], role: Role) -> Vec<usize> {
+    leds.iter().enumerate().filter(|(_, led)| led.role == Some(role)).map(|(i, _)| i).collect()
+}
+
+/// Which LEDs are next to which, for simulations that pass things between neighboring LEDs.
+/// Edges go both ways: if `a` is a neighbor of `b`, `b` is a neighbor of `a`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NeighborGraph {
+    /// for each LED, its neighbors' indices and how far away they are
+    neighbors: Vec<Vec<(usize, f32)>>,
+}
+
+impl NeighborGraph {
+    /// Connect every LED to its `k` nearest LEDs.
+    #[must_use] pub fn k_nearest(leds: &[LED], k: usize) -> Self {
+        let mut graph = Self { neighbors: vec![vec![]; leds.len()] };
+        for i in 0..leds.len() {
+            let mut others: Vec<usize> = (0..leds.len()).filter(|j| *j != i).collect();
+            others.sort_by(|a, b| distance(&leds[i], &leds[*a]).total_cmp(&distance(&leds[i], &leds[*b])));
+            for j in others.into_iter().take(k) {
+                graph.connect(i, j, distance(&leds[i], &leds[j]));
+            }
+        }
+        graph
+    }
+
+    /// Connect every pair of LEDs that are at most `max_distance` apart.
+    #[must_use] pub fn within_distance(leds: &[LED], max_distance: f32) -> Self {
+        let mut graph = Self { neighbors: vec![vec![]; leds.len()] };
+        for i in 0..leds.len() {
+            for j in (i + 1)..leds.len() {
+                let distance = distance(&leds[i], &leds[j]);
+                if distance <= max_distance {
+                    graph.connect(i, j, distance);
+                }
+            }
+        }
+        graph
+    }
+
+    fn connect(&mut self, a: usize, b: usize, distance: f32) {
+        if !self.neighbors[a].iter().any(|(other, _)| *other == b) {
+            self.neighbors[a].push((b, distance));
+            self.neighbors[b].push((a, distance));
+        }
+    }
+
+    /// The neighbors of LED `i`, with how far away each one is.
+    #[must_use] pub fn neighbors(&self, i: usize) -> &[(usize, f32)] {
+        &self.neighbors[i]
+    }
+}
+
+/// Straight-line distance between two LEDs, in layout units.
+#[must_use] pub fn distance(a: &LED, b: &LED) -> f32 {
+    let dx = a.coords.0 as f32 - b.coords.0 as f32;
+    let dy = a.coords.1 as f32 - b.coords.1 as f32;
+    dx.hypot(dy)
+}
@@ -1,51 +1,89 @@
-use std::{env::args, thread};
+use std::thread;
 
 pub use app::App;
+use cli::Options;
 use logging::initialize_logging;
-use types::{Simulation, LED};
+use markov::MarkovModel;
+use playlist::Playlist;
+use recording::Recording;
+use types::{Role, Simulation, LED};
 
 pub mod app;
 pub mod simulations;
 pub mod types;
 pub mod logging;
 pub mod intro;
+pub mod transition;
+pub mod playlist;
+pub mod cli;
+pub mod headless;
+pub mod color;
+pub mod layout;
+pub mod noise;
+pub mod recording;
+pub mod markov;
 
-const LED_POSITIONS: [(usize, usize); 12] = [
-    (103, 4),
-    (104, 11),
-    (105, 17),
-    (106, 24),
-    (110, 30),
-    (115, 24),
-    (118, 17),
-    (119, 10),
-    (120, 3),
-    (112, 3),
-    (111, 11),
-    (111, 18),
+const LAYOUT: [((usize, usize), Role); 12] = [
+    ((103, 4), Role::Base),
+    ((104, 11), Role::Outer),
+    ((105, 17), Role::Outer),
+    ((106, 24), Role::Outer),
+    ((110, 30), Role::Outer),
+    ((115, 24), Role::Outer),
+    ((118, 17), Role::Outer),
+    ((119, 10), Role::Outer),
+    ((120, 3), Role::Base),
+    ((112, 3), Role::Wick),
+    ((111, 11), Role::Flame),
+    ((111, 18), Role::Flame),
 ];
 
 fn main() -> color_eyre::Result<()> {
-    if std::env::args().any(|arg| arg == "--help" || arg == "-h") {
+    color_eyre::install()?;
+    let options = Options::parse(std::env::args().skip(1))?;
+    if options.help {
         println!("{}", intro::TEXT);
         return Ok(());
     }
-    initialize_logging()?;
-    thread::sleep(std::time::Duration::from_secs(1));
-    trace_dbg!("Starting up");
-    let leds: Vec<_> = LED_POSITIONS
+    if let Some(path) = &options.train_markov {
+        let model = MarkovModel::train(&Recording::from_file(path)?);
+        print!("{}", model.to_rust_source());
+        return Ok(());
+    }
+    if !options.headless {
+        // headless output goes to stdout, so don't clutter it with the log location
+        initialize_logging()?;
+        thread::sleep(std::time::Duration::from_secs(1));
+        trace_dbg!("Starting up");
+    }
+    let leds: Vec<_> = LAYOUT
         .iter()
-        .map(|(x, y)| LED {
+        .map(|(coords, role)| LED {
             color: types::RGB { r: 0, g: 0, b: 0 },
-            coords: (*x, *y),
+            coords: *coords,
+            role: Some(*role),
         })
         .collect();
 
-    let simulations: Vec<Box<dyn Simulation>> = simulations::get_simulations(&leds);
+    let mut simulations: Vec<Box<dyn Simulation>> = simulations::get_simulations(&leds);
+    if let Some(path) = &options.recording {
+        simulations.extend(simulations::get_recorded_simulations(&leds, path)?);
+    }
+    let playlist = match &options.playlist {
+        Some(path) => Some(Playlist::from_file(path, &simulations)?),
+        None => None,
+    };
+
+    if options.headless {
+        return headless::run(simulations, leds, playlist, &options);
+    }
 
-    color_eyre::install()?;
     let terminal = ratatui::init();
-    let result = App::new(simulations, leds).run(terminal);
+    let mut app = App::new(simulations, leds);
+    if let Some(playlist) = playlist {
+        app = app.with_playlist(playlist);
+    }
+    let result = app.run(terminal);
     ratatui::restore();
     result
 }
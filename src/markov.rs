@@ -0,0 +1,175 @@
+//! A tiny Markov chain model of flicker, small enough to run on the board.
+//!
+//! Brightness is split into [`STATES`] evenly spaced levels. Every [`MarkovModel::step_micros`] the flame jumps
+//! from its current level to a new one, picked using that level's row of the transition table. A recording
+//! of a real flame takes kilobytes per second; the whole model is one `u32` and `STATES * STATES` bytes.
+use std::fmt::Write;
+
+use rand::Rng;
+
+use crate::recording::Recording;
+
+/// How many brightness levels the model knows about.
+pub const STATES: usize = 8;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MarkovModel {
+    /// how long the flame stays at a level before jumping to the next one
+    pub step_micros: u32,
+    /// `transitions[from][to]` is how likely a jump from level `from` to level `to` is, out of the row's total
+    pub transitions: [[u8; STATES]; STATES],
+}
+
+impl Default for MarkovModel {
+    /// A hand-tuned model of a candle: it mostly burns near full brightness, wanders a level or two at a time,
+    /// and now and then drops a long way before climbing back up.
+    fn default() -> Self {
+        Self {
+            step_micros: 40_000,
+            transitions: [
+                [40, 120, 80, 15, 0, 0, 0, 0],
+                [10, 50, 120, 60, 15, 0, 0, 0],
+                [0, 10, 60, 120, 50, 15, 0, 0],
+                [0, 0, 10, 70, 120, 45, 10, 0],
+                [0, 0, 5, 15, 90, 110, 35, 0],
+                [0, 3, 5, 10, 30, 110, 80, 17],
+                [2, 3, 5, 5, 10, 40, 140, 50],
+                [1, 2, 3, 5, 5, 15, 100, 124],
+            ],
+        }
+    }
+}
+
+impl MarkovModel {
+    /// Learn a model from a recording, averaging its channels together.
+    /// The recording is resampled at its own average sample rate, so unevenly spaced samples are fine.
+    #[must_use] pub fn train(recording: &Recording) -> Self {
+        let step_secs = recording.sample_interval();
+        let steps = (recording.loop_secs() / step_secs).round() as usize;
+        let states: Vec<usize> = (0..steps)
+            .map(|i| {
+                let values = recording.sample_at(i as f32 * step_secs);
+                let brightness = values.iter().sum::<f32>() / values.len() as f32;
+                Self::state_of(brightness)
+            })
+            .collect();
+
+        let mut counts = [[0u32; STATES]; STATES];
+        // the recording loops, so the last state leads back into the first
+        for (i, from) in states.iter().enumerate() {
+            let to = states[(i + 1) % states.len()];
+            counts[*from][to] += 1;
+        }
+
+        let mut transitions = [[0u8; STATES]; STATES];
+        for (from, row) in counts.iter().enumerate() {
+            let total: u32 = row.iter().sum();
+            if total == 0 {
+                // never seen at this level, so if we somehow get here, stay put
+                transitions[from][from] = 255;
+                continue;
+            }
+            for (to, count) in row.iter().enumerate() {
+                // scale each row to fit in a byte, keeping rare jumps possible instead of rounding them to 0
+                let weight = (*count * 255 + total / 2) / total;
+                transitions[from][to] = if *count > 0 { weight.clamp(1, 255) as u8 } else { 0 };
+            }
+        }
+
+        Self { step_micros: (step_secs * 1_000_000.0).round().max(1.0) as u32, transitions }
+    }
+
+    /// Which level a brightness from 0 to 1 falls in.
+    #[must_use] pub fn state_of(brightness: f32) -> usize {
+        ((brightness.clamp(0.0, 1.0) * STATES as f32) as usize).min(STATES - 1)
+    }
+
+    /// The brightness in the middle of a level, from 0 to 1.
+    #[must_use] pub fn brightness_of(state: usize) -> f32 {
+        (state as f32 + 0.5) / STATES as f32
+    }
+
+    /// Pick the level to jump to from `state`.
+    pub fn next_state(&self, state: usize, rng: &mut impl Rng) -> usize {
+        let row = &self.transitions[state];
+        let total: u32 = row.iter().map(|weight| u32::from(*weight)).sum();
+        if total == 0 {
+            return state;
+        }
+        let mut pick = rng.gen_range(0..total);
+        for (to, weight) in row.iter().enumerate() {
+            let weight = u32::from(*weight);
+            if pick < weight {
+                return to;
+            }
+            pick -= weight;
+        }
+        state
+    }
+
+    /// The model as Rust constants, ready to paste into the board's firmware.
+    #[must_use] pub fn to_rust_source(&self) -> String {
+        let mut source = String::new();
+        // writing to a String can't fail
+        let _ = writeln!(source, "pub const MARKOV_STEP_MICROS: u32 = {};", self.step_micros);
+        let _ = writeln!(source, "pub const MARKOV_TRANSITIONS: [[u8; {STATES}]; {STATES}] = [");
+        for row in &self.transitions {
+            let row: Vec<String> = row.iter().map(u8::to_string).collect();
+            let _ = writeln!(source, "    [{}],", row.join(", "));
+        }
+        source.push_str("];\n");
+        source
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trained_on(values: &[f32]) -> MarkovModel {
+        let csv: String = values.iter().enumerate().map(|(i, value)| format!("{i},{value}\n")).collect();
+        MarkovModel::train(&Recording::parse(&csv).unwrap())
+    }
+
+    #[test]
+    fn counts_transitions_including_the_loop_back_to_the_start() {
+        let model = trained_on(&[0.0, 1.0, 0.0, 1.0]);
+        assert_eq!(model.step_micros, 1_000_000);
+        assert_eq!(model.transitions[0][STATES - 1], 255);
+        assert_eq!(model.transitions[STATES - 1][0], 255);
+        assert_eq!(model.transitions[0].iter().map(|weight| u32::from(*weight)).sum::<u32>(), 255);
+    }
+
+    #[test]
+    fn normalizes_each_row_to_a_byte() {
+        // from the bottom level: 3 times to itself, once to the top
+        let model = trained_on(&[0.0, 0.0, 0.0, 0.0, 1.0]);
+        assert_eq!(model.transitions[0][0], 191);
+        assert_eq!(model.transitions[0][STATES - 1], 64);
+        assert_eq!(model.transitions[STATES - 1][0], 255);
+    }
+
+    #[test]
+    fn keeps_rare_jumps_possible() {
+        let mut values = vec![0.0; 600];
+        values.push(1.0);
+        let model = trained_on(&values);
+        assert_eq!(model.transitions[0][STATES - 1], 1);
+    }
+
+    #[test]
+    fn levels_never_seen_stay_put() {
+        let model = trained_on(&[0.0, 1.0]);
+        assert_eq!(model.transitions[3][3], 255);
+        assert_eq!(model.next_state(3, &mut rand::thread_rng()), 3);
+    }
+
+    #[test]
+    fn states_and_brightness_round_trip() {
+        for state in 0..STATES {
+            assert_eq!(MarkovModel::state_of(MarkovModel::brightness_of(state)), state);
+        }
+        assert_eq!(MarkovModel::state_of(-1.0), 0);
+        assert_eq!(MarkovModel::state_of(2.0), STATES - 1);
+    }
+}
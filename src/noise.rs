@@ -0,0 +1,113 @@
+//! Smooth pseudo-random noise for simulations that want organic movement instead of sums of sines.
+//! This is Ken Perlin's "improved noise" (<https://mrl.cs.nyu.edu/~perlin/noise/>), in 3D.
+
+/// A fixed shuffle of 0..=255, so the noise is the same every run.
+const PERMUTATION: [u8; 256] = shuffled_bytes(0x2545_F491);
+
+const fn shuffled_bytes(seed: u32) -> [u8; 256] {
+    let mut table = [0u8; 256];
+    let mut i = 0;
+    while i < 256 {
+        table[i] = i as u8;
+        i += 1;
+    }
+    // Fisher-Yates with a xorshift generator, since we can't use rand in a const
+    let mut state = seed;
+    let mut i = 255;
+    while i > 0 {
+        state ^= state << 13;
+        state ^= state >> 17;
+        state ^= state << 5;
+        let j = (state % (i as u32 + 1)) as usize;
+        let tmp = table[i];
+        table[i] = table[j];
+        table[j] = tmp;
+        i -= 1;
+    }
+    table
+}
+
+fn hash(i: i32) -> i32 {
+    i32::from(PERMUTATION[(i & 255) as usize])
+}
+
+fn fade(t: f32) -> f32 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(t: f32, a: f32, b: f32) -> f32 {
+    a + t * (b - a)
+}
+
+/// Dot product of a pseudo-random gradient with (x, y, z).
+fn grad(hash: i32, x: f32, y: f32, z: f32) -> f32 {
+    let h = hash & 15;
+    let u = if h < 8 { x } else { y };
+    let v = if h < 4 {
+        y
+    } else if h == 12 || h == 14 {
+        x
+    } else {
+        z
+    };
+    (if h & 1 == 0 { u } else { -u }) + (if h & 2 == 0 { v } else { -v })
+}
+
+/// 3D Perlin noise. Smooth, roughly between -1 and 1, and 0 at every integer point.
+#[must_use] pub fn perlin3(x: f32, y: f32, z: f32) -> f32 {
+    let xi = x.floor() as i32;
+    let yi = y.floor() as i32;
+    let zi = z.floor() as i32;
+    let x = x - x.floor();
+    let y = y - y.floor();
+    let z = z - z.floor();
+    let u = fade(x);
+    let v = fade(y);
+    let w = fade(z);
+
+    let a = hash(xi) + yi;
+    let aa = hash(a) + zi;
+    let ab = hash(a + 1) + zi;
+    let b = hash(xi + 1) + yi;
+    let ba = hash(b) + zi;
+    let bb = hash(b + 1) + zi;
+
+    lerp(
+        w,
+        lerp(
+            v,
+            lerp(u, grad(hash(aa), x, y, z), grad(hash(ba), x - 1.0, y, z)),
+            lerp(u, grad(hash(ab), x, y - 1.0, z), grad(hash(bb), x - 1.0, y - 1.0, z)),
+        ),
+        lerp(
+            v,
+            lerp(u, grad(hash(aa + 1), x, y, z - 1.0), grad(hash(ba + 1), x - 1.0, y, z - 1.0)),
+            lerp(
+                u,
+                grad(hash(ab + 1), x, y - 1.0, z - 1.0),
+                grad(hash(bb + 1), x - 1.0, y - 1.0, z - 1.0),
+            ),
+        ),
+    )
+}
+
+/// Fractal noise: several octaves of `perlin3` at doubling frequency and halving amplitude,
+/// for detail at more than one scale. Roughly between -1 and 1.
+#[must_use] pub fn fbm3(x: f32, y: f32, z: f32, octaves: u32) -> f32 {
+    let mut total = 0.0;
+    let mut amplitude = 1.0;
+    let mut frequency = 1.0;
+    let mut max_amplitude = 0.0;
+    for _ in 0..octaves {
+        total += perlin3(x * frequency, y * frequency, z * frequency) * amplitude;
+        max_amplitude += amplitude;
+        amplitude *= 0.5;
+        frequency *= 2.0;
+    }
+    total / max_amplitude
+}
+
+/// 1D noise, for things like a single value wandering over time. `channel` picks an independent stream.
+#[must_use] pub fn perlin1(t: f32, channel: f32) -> f32 {
+    perlin3(t, channel * 7.31 + 0.5, 0.5)
+}
@@ -0,0 +1,395 @@
+//! Playlists cycle through a list of simulations, each with its own duration, intensity and preset.
+//! They can be built from the menu or loaded from a file, and are used by both the UI and headless rendering.
+use std::{path::Path, time::Duration};
+
+use color_eyre::eyre::{bail, eyre, Result, WrapErr};
+
+use crate::{
+    transition::{Transition, TransitionStyle},
+    types::{Simulation, LED},
+};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct PlaylistEntry {
+    /// index into the list of simulations
+    pub simulation: usize,
+    pub duration: Duration,
+    pub intensity_mod: f32,
+    /// preset to apply when the entry starts, if any
+    pub preset: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct Playlist {
+    entries: Vec<PlaylistEntry>,
+    current: usize,
+    /// when the current entry started, in simulation micros. None while the transition into it is still waiting
+    /// for the previous entry's ending, so the wait doesn't eat into the entry's time
+    entry_start: Option<u64>,
+    transition: Option<Transition>,
+    transition_style: TransitionStyle,
+    transition_duration: Duration,
+}
+
+impl Playlist {
+    /// The longest an entry in a playlist file can last: a day.
+    pub const MAX_ENTRY_DURATION: Duration = Duration::from_secs(24 * 60 * 60);
+
+    #[must_use] pub const fn new(entries: Vec<PlaylistEntry>) -> Self {
+        Self {
+            entries,
+            current: 0,
+            entry_start: None,
+            transition: None,
+            transition_style: TransitionStyle::Crossfade,
+            transition_duration: Duration::from_secs(2),
+        }
+    }
+
+    /// Set how the playlist transitions between entries.
+    pub const fn set_transition(&mut self, style: TransitionStyle, duration: Duration) {
+        self.transition_style = style;
+        self.transition_duration = duration;
+    }
+
+    /// Load a playlist from a file. See [`Playlist::parse`] for the format.
+    pub fn from_file(path: &Path, simulations: &[Box<dyn Simulation>]) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .wrap_err_with(|| format!("couldn't read playlist {}", path.display()))?;
+        Self::parse(&text, simulations).wrap_err_with(|| format!("couldn't parse playlist {}", path.display()))
+    }
+
+    /// Parse a playlist. Each line is an entry of the form
+    /// `simulation name | seconds | intensity | preset`, where intensity (default 1.0) and preset are optional.
+    /// Blank lines and lines starting with `#` are ignored.
+    pub fn parse(text: &str, simulations: &[Box<dyn Simulation>]) -> Result<Self> {
+        let mut entries = vec![];
+        for (line_num, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let fields: Vec<&str> = line.split('|').map(str::trim).collect();
+            let entry = Self::parse_entry(&fields, simulations).wrap_err_with(|| format!("on line {}", line_num + 1))?;
+            entries.push(entry);
+        }
+        if entries.is_empty() {
+            bail!("playlist has no entries");
+        }
+        Ok(Self::new(entries))
+    }
+
+    fn parse_entry(fields: &[&str], simulations: &[Box<dyn Simulation>]) -> Result<PlaylistEntry> {
+        if fields.len() < 2 || fields.len() > 4 {
+            bail!("expected `simulation name | seconds | intensity | preset`");
+        }
+        let simulation = simulations
+            .iter()
+            .position(|simulation| simulation.get_name() == fields[0])
+            .ok_or_else(|| eyre!("no simulation named {:?}", fields[0]))?;
+        let seconds: f32 = fields[1].parse().wrap_err("duration should be a number of seconds")?;
+        if !seconds.is_finite() || seconds <= 0.0 {
+            bail!("duration should be more than 0 seconds");
+        }
+        let duration = Duration::try_from_secs_f32(seconds).wrap_err("duration is out of range")?;
+        if duration > Self::MAX_ENTRY_DURATION {
+            bail!("duration should be at most {} seconds", Self::MAX_ENTRY_DURATION.as_secs());
+        }
+        let intensity_mod = match fields.get(2) {
+            Some(intensity) if !intensity.is_empty() => intensity.parse().wrap_err("intensity should be a number")?,
+            _ => 1.0,
+        };
+        if !(0.0..=1.0).contains(&intensity_mod) {
+            bail!("intensity should be between 0 and 1");
+        }
+        let preset = match fields.get(3) {
+            Some(preset) if !preset.is_empty() => {
+                if !simulations[simulation].get_presets().contains(preset) {
+                    bail!("{:?} has no preset named {:?}", fields[0], preset);
+                }
+                Some((*preset).to_owned())
+            }
+            _ => None,
+        };
+        Ok(PlaylistEntry { simulation, duration, intensity_mod, preset })
+    }
+
+    #[must_use] pub fn entries(&self) -> &[PlaylistEntry] {
+        &self.entries
+    }
+
+    #[must_use] pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn push(&mut self, entry: PlaylistEntry) {
+        self.entries.push(entry);
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.current = 0;
+        self.transition = None;
+    }
+
+    #[must_use] pub const fn current_index(&self) -> usize {
+        self.current
+    }
+
+    #[must_use] pub fn current_entry(&self) -> &PlaylistEntry {
+        &self.entries[self.current]
+    }
+
+    /// The total length of one pass through the playlist, or [`Duration::MAX`] if it's too long to count.
+    #[must_use] pub fn total_duration(&self) -> Duration {
+        self.entries.iter().fold(Duration::ZERO, |total, entry| total.saturating_add(entry.duration))
+    }
+
+    /// The transition currently running between two entries, if any.
+    #[must_use] pub const fn transition(&self) -> Option<&Transition> {
+        self.transition.as_ref()
+    }
+
+    /// How far through the current entry we are, from 0 to 1.
+    #[must_use] pub fn progress(&self, micros: u64) -> f32 {
+        let Some(entry_start) = self.entry_start else {
+            return 0.0;
+        };
+        let elapsed = micros.saturating_sub(entry_start) as f32 / 1_000_000.0;
+        (elapsed / self.current_entry().duration.as_secs_f32()).min(1.0)
+    }
+
+    /// Start playing from the first entry at `micros`.
+    /// `presets` holds the preset applied to each simulation, and is kept up to date as entries apply theirs.
+    pub fn start(&mut self, simulations: &mut [Box<dyn Simulation>], presets: &mut [Option<usize>], micros: u64) {
+        self.current = 0;
+        self.entry_start = Some(micros);
+        self.transition = None;
+        self.apply_preset(simulations, presets);
+        simulations[self.current_entry().simulation].start(micros);
+    }
+
+    /// Move on to the next entry, transitioning into it if it's a different simulation.
+    pub fn advance(&mut self, simulations: &mut [Box<dyn Simulation>], presets: &mut [Option<usize>], leds: &[LED], micros: u64) {
+        // if we're skipping ahead mid-transition, the transition is dropped and the mixed frame in `leds`
+        // becomes the starting point for the next one
+        self.transition = None;
+        let from = self.current_entry().simulation;
+        self.current = (self.current + 1) % self.entries.len();
+        self.entry_start = Some(micros);
+        self.apply_preset(simulations, presets);
+        let to = self.current_entry().simulation;
+        if from != to {
+            // the entry's time starts when the blend into it does, see `tick`
+            self.entry_start = None;
+            self.transition = Some(Transition::new(
+                simulations,
+                from,
+                to,
+                self.transition_style,
+                self.transition_duration,
+                micros,
+                leds,
+            ));
+        }
+    }
+
+    /// Tick whatever the playlist is currently showing into `leds`, moving on to the next entry when it's time.
+    pub fn tick(&mut self, simulations: &mut [Box<dyn Simulation>], presets: &mut [Option<usize>], leds: &mut Vec<LED>, micros: u64) {
+        if self.progress(micros) >= 1.0 {
+            self.advance(simulations, presets, leds, micros);
+        }
+        let intensity_mod = self.current_entry().intensity_mod;
+        if let Some(transition) = &mut self.transition {
+            // ease between the two entries' intensities as well as their colors
+            let previous = (self.current + self.entries.len() - 1) % self.entries.len();
+            let previous_intensity = self.entries[previous].intensity_mod;
+            let progress = transition.progress(micros);
+            let intensity_mod = previous_intensity + (intensity_mod - previous_intensity) * progress;
+            transition.tick(simulations, leds, micros, intensity_mod);
+            if self.entry_start.is_none() {
+                self.entry_start = transition.started_at();
+            }
+            if transition.is_finished(micros) {
+                *leds = self.transition.take().unwrap().into_leds();
+            }
+        } else {
+            simulations[self.current_entry().simulation].tick(leds, micros, intensity_mod);
+        }
+    }
+
+    /// Apply the current entry's preset, or the simulation's first (default) preset if the entry doesn't name one,
+    /// so an entry doesn't inherit whatever preset an earlier entry left behind.
+    fn apply_preset(&self, simulations: &mut [Box<dyn Simulation>], presets: &mut [Option<usize>]) {
+        let entry = self.current_entry();
+        let simulation = &mut simulations[entry.simulation];
+        let available = simulation.get_presets();
+        let preset = match &entry.preset {
+            Some(preset) => available.iter().position(|available| available == preset),
+            None if !available.is_empty() => Some(0),
+            None => None,
+        };
+        if let Some(preset) = preset {
+            simulation.apply_preset(available[preset]);
+            presets[entry.simulation] = Some(preset);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug)]
+    struct PresetSim {
+        preset: &'static str,
+    }
+
+    impl Simulation for PresetSim {
+        fn tick(&mut self, _leds: &mut Vec<LED>, _micros: u64, _intensity_mod: f32) {}
+
+        fn new(_leds: &[LED]) -> Self where Self: Sized {
+            Self { preset: "Calm" }
+        }
+
+        fn get_name(&self) -> &str {
+            "Preset sim"
+        }
+
+        fn get_presets(&self) -> &'static [&'static str] {
+            &["Calm", "Windy"]
+        }
+
+        fn apply_preset(&mut self, preset: &str) -> bool {
+            self.preset = match preset {
+                "Calm" => "Calm",
+                "Windy" => "Windy",
+                _ => return false,
+            };
+            true
+        }
+
+        fn get_status(&self) -> Option<String> {
+            Some(self.preset.to_owned())
+        }
+    }
+
+    /// Takes three seconds to play its ending once stopped.
+    #[derive(Debug)]
+    struct SlowEndingSim {
+        last_tick: u64,
+        stopped_at: Option<u64>,
+    }
+
+    impl Simulation for SlowEndingSim {
+        fn tick(&mut self, _leds: &mut Vec<LED>, micros: u64, _intensity_mod: f32) {
+            self.last_tick = micros;
+        }
+
+        fn new(_leds: &[LED]) -> Self where Self: Sized {
+            Self { last_tick: 0, stopped_at: None }
+        }
+
+        fn get_name(&self) -> &str {
+            "Slow ending sim"
+        }
+
+        fn start(&mut self, _micros: u64) {
+            self.stopped_at = None;
+        }
+
+        fn stop(&mut self, micros: u64) {
+            self.stopped_at = Some(micros);
+        }
+
+        fn is_stopped(&self) -> bool {
+            self.stopped_at.is_none_or(|stopped_at| self.last_tick >= stopped_at + 3_000_000)
+        }
+    }
+
+    fn simulations() -> Vec<Box<dyn Simulation>> {
+        vec![Box::new(PresetSim::new(&[]))]
+    }
+
+    #[test]
+    fn parses_good_input() {
+        let text = "# a comment\n\nPreset sim | 5\nPreset sim | 2.5 | 0.3 | Windy\nPreset sim | 1 | | Calm\n";
+        let playlist = Playlist::parse(text, &simulations()).unwrap();
+        assert_eq!(playlist.entries(), &[
+            PlaylistEntry { simulation: 0, duration: Duration::from_secs(5), intensity_mod: 1.0, preset: None },
+            PlaylistEntry { simulation: 0, duration: Duration::from_millis(2500), intensity_mod: 0.3, preset: Some("Windy".to_owned()) },
+            PlaylistEntry { simulation: 0, duration: Duration::from_secs(1), intensity_mod: 1.0, preset: Some("Calm".to_owned()) },
+        ]);
+    }
+
+    #[test]
+    fn rejects_missing_fields() {
+        assert!(Playlist::parse("Preset sim", &simulations()).is_err());
+        assert!(Playlist::parse("Preset sim | 5 | 1 | Calm | extra", &simulations()).is_err());
+        assert!(Playlist::parse("# nothing but comments", &simulations()).is_err());
+    }
+
+    #[test]
+    fn rejects_unknown_simulations_and_bad_durations() {
+        assert!(Playlist::parse("Nonexistent sim | 5", &simulations()).is_err());
+        assert!(Playlist::parse("Preset sim | soon", &simulations()).is_err());
+        assert!(Playlist::parse("Preset sim | 0", &simulations()).is_err());
+        assert!(Playlist::parse("Preset sim | 1e20", &simulations()).is_err());
+        assert!(Playlist::parse("Preset sim | 86401", &simulations()).is_err());
+        assert!(Playlist::parse("Preset sim | 86400", &simulations()).is_ok());
+    }
+
+    #[test]
+    fn total_duration_saturates_instead_of_overflowing() {
+        let entry = PlaylistEntry { simulation: 0, duration: Duration::MAX, intensity_mod: 1.0, preset: None };
+        let playlist = Playlist::new(vec![entry.clone(), entry]);
+        assert_eq!(playlist.total_duration(), Duration::MAX);
+    }
+
+    #[test]
+    fn rejects_a_bad_intensity() {
+        assert!(Playlist::parse("Preset sim | 5 | loud", &simulations()).is_err());
+        assert!(Playlist::parse("Preset sim | 5 | 1.5", &simulations()).is_err());
+    }
+
+    #[test]
+    fn rejects_an_unknown_preset() {
+        assert!(Playlist::parse("Preset sim | 5 | 1 | Stormy", &simulations()).is_err());
+    }
+
+    #[test]
+    fn an_entry_without_a_preset_goes_back_to_the_default() {
+        let mut simulations = simulations();
+        let mut presets = vec![None];
+        let mut playlist = Playlist::parse("Preset sim | 1 | 1 | Windy\nPreset sim | 1", &simulations).unwrap();
+        playlist.start(&mut simulations, &mut presets, 0);
+        assert_eq!(simulations[0].get_status().as_deref(), Some("Windy"));
+        assert_eq!(presets, [Some(1)]);
+
+        playlist.advance(&mut simulations, &mut presets, &[], 1_000_000);
+        assert_eq!(simulations[0].get_status().as_deref(), Some("Calm"));
+        assert_eq!(presets, [Some(0)]);
+    }
+
+    #[test]
+    fn an_entry_starts_counting_once_the_previous_one_has_ended() {
+        let mut simulations: Vec<Box<dyn Simulation>> = vec![Box::new(SlowEndingSim::new(&[])), Box::new(PresetSim::new(&[]))];
+        let mut presets = vec![None; 2];
+        let mut leds = vec![];
+        let mut playlist = Playlist::parse("Slow ending sim | 1\nPreset sim | 1", &simulations).unwrap();
+        playlist.start(&mut simulations, &mut presets, 0);
+
+        // the first entry is over, but its ending is still playing
+        playlist.tick(&mut simulations, &mut presets, &mut leds, 1_000_000);
+        playlist.tick(&mut simulations, &mut presets, &mut leds, 3_000_000);
+        assert_eq!(playlist.current_index(), 1);
+        assert_eq!(playlist.progress(3_000_000), 0.0);
+
+        // the ending is done, so the blend and the second entry's time start together
+        playlist.tick(&mut simulations, &mut presets, &mut leds, 4_000_000);
+        assert_eq!(playlist.transition().and_then(Transition::started_at), Some(4_000_000));
+        assert!((playlist.progress(4_500_000) - 0.5).abs() < 1e-6);
+        playlist.tick(&mut simulations, &mut presets, &mut leds, 4_500_000);
+        assert_eq!(playlist.current_index(), 1);
+    }
+}
@@ -0,0 +1,182 @@
+//! Brightness measured from a real flame, loaded from a CSV of `seconds, value, value, ...` rows.
+//! Each column is one sensor's channel, and is stretched to 0-1 on its own since the raw units depend on the sensor.
+use std::path::Path;
+
+use color_eyre::eyre::{bail, eyre, Result, WrapErr};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Recording {
+    timestamps: Vec<f32>, // seconds, starting at 0
+    samples: Vec<Vec<f32>>, // one row per timestamp, one 0-1 value per channel
+    loop_secs: f32, // how long one pass through the recording takes, including easing back to the start
+}
+
+impl Recording {
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .wrap_err_with(|| format!("couldn't read recording {}", path.display()))?;
+        Self::parse(&text).wrap_err_with(|| format!("couldn't parse recording {}", path.display()))
+    }
+
+    /// Parse a recording. Each line is `seconds, value, value, ...` with the same number of values on every line.
+    /// A header line (a first line without any numbers), blank lines and lines starting with `#` are ignored.
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut rows: Vec<(f32, Vec<f32>)> = vec![];
+        for (line_num, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            // a header has no numbers in it. a first line with some numbers is a row, and gets parsed (and reported) like one
+            let is_header = rows.is_empty() && fields.iter().all(|field| field.parse::<f32>().is_err());
+            if is_header {
+                continue;
+            }
+            let row = Self::parse_row(&fields).wrap_err_with(|| format!("on line {}", line_num + 1))?;
+            if let Some((last_time, last_values)) = rows.last() {
+                if row.0 <= *last_time {
+                    bail!("on line {}: timestamps should go up from one line to the next", line_num + 1);
+                }
+                if row.1.len() != last_values.len() {
+                    bail!("on line {}: expected {} values, found {}", line_num + 1, last_values.len(), row.1.len());
+                }
+            }
+            rows.push(row);
+        }
+        if rows.len() < 2 {
+            bail!("recording needs at least 2 samples");
+        }
+
+        // stretch each channel to 0-1
+        let channels = rows[0].1.len();
+        for channel in 0..channels {
+            let min = rows.iter().map(|(_, values)| values[channel]).fold(f32::MAX, f32::min);
+            let max = rows.iter().map(|(_, values)| values[channel]).fold(f32::MIN, f32::max);
+            let range = if max > min { max - min } else { 1.0 };
+            for (_, values) in &mut rows {
+                values[channel] = (values[channel] - min) / range;
+            }
+        }
+
+        let start = rows[0].0;
+        let timestamps: Vec<f32> = rows.iter().map(|(time, _)| time - start).collect();
+        let loop_secs = timestamps[timestamps.len() - 1] + Self::average_interval(&timestamps); // ease back to the start over one sample
+        Ok(Self { timestamps, samples: rows.into_iter().map(|(_, values)| values).collect(), loop_secs })
+    }
+
+    fn parse_row(fields: &[&str]) -> Result<(f32, Vec<f32>)> {
+        // `parse` takes "NaN" and "inf" too, which would break the ordering of timestamps and the scaling of channels
+        let parse_finite = |field: &str| field.parse::<f32>().ok().filter(|value| value.is_finite());
+        let time = parse_finite(fields[0]).ok_or_else(|| eyre!("timestamp {:?} should be a number", fields[0]))?;
+        let values = fields[1..]
+            .iter()
+            .map(|field| parse_finite(field).ok_or_else(|| eyre!("value {field:?} should be a number")))
+            .collect::<Result<Vec<f32>>>()?;
+        if values.is_empty() {
+            bail!("expected a timestamp and at least one value");
+        }
+        Ok((time, values))
+    }
+
+    fn average_interval(timestamps: &[f32]) -> f32 {
+        timestamps[timestamps.len() - 1] / (timestamps.len() - 1) as f32
+    }
+
+    /// A recording that holds `value` on one channel forever.
+    #[must_use] pub fn steady(value: f32) -> Self {
+        Self { timestamps: vec![0.0], samples: vec![vec![value]], loop_secs: 1.0 }
+    }
+
+    #[must_use] pub fn len(&self) -> usize {
+        self.timestamps.len()
+    }
+
+    #[must_use] pub fn is_empty(&self) -> bool {
+        self.timestamps.is_empty()
+    }
+
+    #[must_use] pub fn channels(&self) -> usize {
+        self.samples[0].len()
+    }
+
+    /// How long one pass through the recording takes before it starts over, in seconds.
+    #[must_use] pub fn loop_secs(&self) -> f32 {
+        self.loop_secs
+    }
+
+    /// How far apart the samples are on average, in seconds.
+    #[must_use] pub fn sample_interval(&self) -> f32 {
+        if self.timestamps.len() < 2 { self.loop_secs } else { Self::average_interval(&self.timestamps) }
+    }
+
+    /// Every channel's value `secs` into the recording, interpolated between the samples on either side.
+    /// Past the end, the recording eases back to the first sample and starts over.
+    #[must_use] pub fn sample_at(&self, secs: f32) -> Vec<f32> {
+        let secs = secs.rem_euclid(self.loop_secs);
+        // the first sample after `secs`. past the last sample, we're easing back to the first one
+        let next = self.timestamps.partition_point(|time| *time <= secs);
+        let (prev, next, prev_time, next_time) = if next == self.timestamps.len() {
+            (next - 1, 0, self.timestamps[next - 1], self.loop_secs)
+        } else {
+            (next - 1, next, self.timestamps[next - 1], self.timestamps[next])
+        };
+        let amount = (secs - prev_time) / (next_time - prev_time);
+        self.samples[prev]
+            .iter()
+            .zip(&self.samples[next])
+            .map(|(a, b)| a + (b - a) * amount)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_a_recording_with_a_header() {
+        let recording = Recording::parse("seconds,left,right\n# a comment\n\n0.0, 500, 10\n0.5, 600, 30\n1.0, 550, 20\n").unwrap();
+        assert_eq!(recording.len(), 3);
+        assert_eq!(recording.channels(), 2);
+        assert_eq!(recording.loop_secs(), 1.5);
+        assert_eq!(recording.sample_at(0.0), vec![0.0, 0.0]);
+        assert_eq!(recording.sample_at(0.5), vec![1.0, 1.0]);
+        assert_eq!(recording.sample_at(0.25), vec![0.5, 0.5]);
+    }
+
+    #[test]
+    fn timestamps_start_at_zero_and_loop() {
+        let recording = Recording::parse("10,0\n11,1\n").unwrap();
+        assert_eq!(recording.sample_at(1.0), vec![1.0]);
+        // easing back to the start after the last sample
+        assert_eq!(recording.sample_at(1.5), vec![0.5]);
+        assert_eq!(recording.sample_at(2.0), vec![0.0]);
+    }
+
+    #[test]
+    fn reports_a_malformed_first_row() {
+        let error = Recording::parse("0.0, bright\n0.5, 600\n").unwrap_err();
+        assert!(format!("{error:?}").contains("line 1"));
+    }
+
+    #[test]
+    fn rejects_values_that_arent_finite() {
+        assert!(Recording::parse("0,1\nNaN,2\n1,3\n").is_err());
+        assert!(Recording::parse("0,1\n1,inf\n").is_err());
+        assert!(Recording::parse("0,1\n1,-inf\n").is_err());
+    }
+
+    #[test]
+    fn rejects_out_of_order_timestamps() {
+        assert!(Recording::parse("0,1\n1,2\n1,3\n").is_err());
+        assert!(Recording::parse("0,1\n2,2\n1,3\n").is_err());
+    }
+
+    #[test]
+    fn rejects_mismatched_and_missing_values() {
+        assert!(Recording::parse("0,1,2\n1,3\n").is_err());
+        assert!(Recording::parse("0\n1\n").is_err());
+        assert!(Recording::parse("0,1\n").is_err());
+    }
+}
@@ -1,12 +1,90 @@
-use std::vec;
+use std::{path::Path, time::Duration, vec};
 
-use crate::types::{Simulation, LED};
+use color_eyre::eyre::Result;
+
+use crate::{
+    markov::MarkovModel,
+    recording::Recording,
+    types::{Simulation, LED},
+};
+
+/// Forwards `Simulation` methods to the simulation a wrapper keeps in the field `$inner`, so wrappers only write
+/// the methods they change. `delegate_simulation!(inner)` forwards everything but `new`, `get_name` and `tick`;
+/// wrappers with their own start or stop list just the methods they forward, like
+/// `delegate_simulation!(inner => get_presets, apply_preset, get_status)`.
+macro_rules! delegate_simulation {
+    ($inner:ident) => {
+        delegate_simulation!($inner => get_presets, apply_preset, get_status, start, stop, is_stopped, lit_by_power_on);
+    };
+    ($inner:ident => $($method:ident),+ $(,)?) => {
+        $(delegate_simulation!(@ $inner, $method);)+
+    };
+    (@ $inner:ident, get_presets) => {
+        fn get_presets(&self) -> &'static [&'static str] {
+            self.$inner.get_presets()
+        }
+    };
+    (@ $inner:ident, apply_preset) => {
+        fn apply_preset(&mut self, preset: &str) -> bool {
+            self.$inner.apply_preset(preset)
+        }
+    };
+    (@ $inner:ident, get_status) => {
+        fn get_status(&self) -> Option<String> {
+            self.$inner.get_status()
+        }
+    };
+    (@ $inner:ident, start) => {
+        fn start(&mut self, micros: u64) {
+            self.$inner.start(micros);
+        }
+    };
+    (@ $inner:ident, stop) => {
+        fn stop(&mut self, micros: u64) {
+            self.$inner.stop(micros);
+        }
+    };
+    (@ $inner:ident, is_stopped) => {
+        fn is_stopped(&self) -> bool {
+            self.$inner.is_stopped()
+        }
+    };
+    (@ $inner:ident, lit_by_power_on) => {
+        fn lit_by_power_on(&self) -> bool {
+            self.$inner.lit_by_power_on()
+        }
+    };
+}
 
 mod always_on_sim;
 mod flash_every_second;
 mod rainbow_flood;
 mod height_flicker;
 mod candle_colored;
+mod layered;
+mod decorators;
+mod lifecycle;
+mod fire2012;
+mod noise_flame;
+mod sparks;
+mod heat_graph;
+mod fluid_flame;
+mod spring_tip;
+mod guttering;
+mod embers;
+mod campfire;
+mod gas_burner;
+mod torch_wind;
+mod match_strike;
+mod recorded;
+mod markov_flicker;
+mod correlated_flicker;
+mod forest_fire;
+mod lantern;
+mod fireplace;
+mod smoke;
+
+pub use match_strike::Strike;
 
 #[must_use] pub fn get_simulations(leds: &[LED]) -> Vec<Box<dyn Simulation>> {
     vec![
@@ -15,5 +93,52 @@ mod candle_colored;
         Box::new(rainbow_flood::RainbowFloodSim::new(leds)),
         Box::new(height_flicker::HeightFlickerSim::new(leds)),
         Box::new(candle_colored::CandleColoredSim::new(leds)),
+        Box::new(layered::LayeredSim::new(leds)),
+        Box::new(fire2012::Fire2012Sim::new(leds)),
+        Box::new(noise_flame::NoiseFlameSim::new(leds)),
+        Box::new(sparks::SparksSim::new(leds)),
+        Box::new(heat_graph::HeatGraphSim::new(leds)),
+        Box::new(fluid_flame::FluidFlameSim::new(leds)),
+        Box::new(fluid_flame::FluidFlameSim::new(leds).with_performance_report()),
+        Box::new(spring_tip::SpringTipSim::new(leds)),
+        Box::new(guttering::GutteringCandleSim::new(leds)),
+        Box::new(embers::EmbersSim::new(leds)),
+        Box::new(campfire::CampfireSim::new(leds)),
+        Box::new(gas_burner::GasBurnerSim::new(leds)),
+        Box::new(torch_wind::TorchWindSim::new(leds)),
+        Box::new(markov_flicker::MarkovFlickerSim::new(leds)),
+        Box::new(correlated_flicker::CorrelatedFlickerSim::new(leds)),
+        Box::new(forest_fire::ForestFireSim::new(leds)),
+        Box::new(lantern::LanternSim::new(leds)),
+        Box::new(fireplace::FireplaceSim::new(leds)),
+        Box::new(smoke::SmokeWispSim::new(leds)),
+        Box::new(lifecycle::Lifecycle::<candle_colored::CandleColoredSim>::new(leds).with_smoke()),
+        Box::new(lifecycle::Lifecycle::<layered::LayeredSim>::new(leds).with_durations(Duration::from_secs(5), Duration::from_secs(6))),
+        Box::new(match_strike::MatchStrike::new(leds)),
+        Box::new(match_strike::MatchStrike::wrap(Box::new(campfire::CampfireSim::new(leds)), leds)),
+        // variations of the simulations above
+        Box::new(decorators::TimeWarp::<candle_colored::CandleColoredSim>::new(leds).with_speed(0.5)),
+        Box::new(decorators::Mirror::<candle_colored::CandleColoredSim>::new(leds)),
+        Box::new(decorators::BrightnessLfo::<candle_colored::CandleColoredSim>::new(leds)),
+        Box::new(decorators::Saturation::wrap(candle_colored::CandleColoredSim::new(leds), leds, 1.5)),
+        Box::new(decorators::HueShift::wrap(height_flicker::HeightFlickerSim::new(leds), leds, 200.0)),
+        Box::new(decorators::BrightnessLfo::wrap(
+            decorators::TimeWarp::<height_flicker::HeightFlickerSim>::new(leds).with_speed(0.25).with_offset(Duration::from_secs(5)),
+            leds,
+            Duration::from_secs(10),
+            0.8,
+        )),
     ]
-}
\ No newline at end of file
+}
+
+/// Load a recording of a real flame (see `recording.rs` for the format), and make simulations that play it back
+/// and that flicker like it using a Markov chain trained on it.
+pub fn get_recorded_simulations(leds: &[LED], path: &Path) -> Result<Vec<Box<dyn Simulation>>> {
+    let recording = Recording::from_file(path)?;
+    let file_name = path.file_name().map_or_else(|| path.display().to_string(), |name| name.to_string_lossy().into_owned());
+    let model = MarkovModel::train(&recording);
+    Ok(vec![
+        Box::new(recorded::RecordedFlickerSim::from_recording(leds, recording, &file_name)),
+        Box::new(markov_flicker::MarkovFlickerSim::from_model(leds, model, &format!("trained on {file_name}"))),
+    ])
+}
@@ -1,5 +1,6 @@
 use crate::types::{Simulation, RGB, LED};
 
+#[allow(dead_code, reason = "only used for testing, see get_simulations")]
 #[derive(Debug)]
 pub struct AlwaysOnSim;
 
@@ -23,4 +24,8 @@ impl Simulation for AlwaysOnSim {
     fn get_name(&self) -> &'static str {
         "Always on"
     }
+
+    fn lit_by_power_on(&self) -> bool {
+        false // a test pattern, not a flame
+    }
 }
\ No newline at end of file
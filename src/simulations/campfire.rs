@@ -0,0 +1,102 @@
+use crate::{
+    color::heat_color,
+    layout::Bounds,
+    noise::perlin1,
+    types::{frame_dt, Simulation, LED},
+};
+
+// a campfire isn't one flame, it's several tongues of flame side by side, each licking up on its own
+// schedule and merging with its neighbors where they overlap.
+// the tongues are spread across the layout, and a hotter fire has more of them going at once.
+// each one follows its own noise stream for height and sway, so they never move in step.
+
+const MAX_TONGUES: usize = 5;
+
+#[derive(Debug)]
+struct Tongue {
+    home_x: f32, // where along the layout this tongue stands, in layout units
+    channel: f32, // which noise stream drives this tongue
+    speed: f32, // how fast it licks up and down, in noise units per second
+    strength: f32, // how lit it is, 0 to 1. eases toward 1 or 0 as tongues are turned on or off
+}
+
+#[derive(Debug)]
+pub struct CampfireSim {
+    last_tick: u64,
+    tongues: Vec<Tongue>,
+    bounds: Bounds,
+}
+
+impl Simulation for CampfireSim {
+    fn new(leds: &[LED]) -> Self where Self: Sized {
+        let bounds = Bounds::of(leds);
+        // spread the tongues evenly, then reorder them from the middle outward so the fire grows from the
+        // center when more of them light up
+        let mut tongues: Vec<Tongue> = (0..MAX_TONGUES)
+            .map(|i| Tongue {
+                home_x: bounds.min_x + bounds.width() * (i as f32 + 0.5) / MAX_TONGUES as f32,
+                channel: i as f32 * 3.7,
+                speed: 1.6 + 0.35 * i as f32,
+                strength: 0.0,
+            })
+            .collect();
+        let (center_x, _) = bounds.center();
+        tongues.sort_by(|a, b| (a.home_x - center_x).abs().total_cmp(&(b.home_x - center_x).abs()));
+        Self { last_tick: 0, tongues, bounds }
+    }
+
+    fn get_name(&self) -> &str {
+        "Campfire"
+    }
+
+    fn tick(
+        &mut self,
+        leds: &mut Vec<LED>,
+        micros: u64,
+        intensity_mod: f32,
+    ) {
+        let active_tongues = 1.0 + intensity_mod * (MAX_TONGUES - 1) as f32; // config: how many tongues burn at this intensity
+        let fade_speed = 1.5; // config: how quickly tongues light up or die down as the intensity changes, per second
+        let tongue_width = self.bounds.width() / MAX_TONGUES as f32 * 1.2; // config: how wide each tongue is at its base
+        let min_height = 0.6; // config: the shortest a tongue gets, as a fraction of the layout height
+        let max_height = 1.6; // config: the tallest a tongue gets, as a fraction of the layout height
+        let sway = 0.4; // config: how far a tongue's tip wanders sideways, as a multiple of its width
+
+        let dt = frame_dt(self.last_tick, micros, 0.1);
+        self.last_tick = micros;
+        let t = micros as f32 / 1_000_000.0;
+
+        for (i, tongue) in self.tongues.iter_mut().enumerate() {
+            // the last tongue to light gets whatever fraction is left over, so turning up the intensity
+            // fades a new tongue in instead of popping it on
+            let target = (active_tongues - i as f32).clamp(0.0, 1.0);
+            let step = fade_speed * dt;
+            tongue.strength += (target - tongue.strength).clamp(-step, step);
+        }
+
+        for led in leds.iter_mut() {
+            let x = led.coords.0 as f32;
+            let y = led.coords.1 as f32;
+            // where tongues overlap they merge: combine them like overlapping light, 1 - (1 - a)(1 - b)...
+            let mut unlit = 1.0;
+            for tongue in &self.tongues {
+                if tongue.strength == 0.0 {
+                    continue;
+                }
+                let lick = perlin1(t * tongue.speed, tongue.channel) + 0.5; // roughly 0 to 1
+                let height = (min_height + (max_height - min_height) * lick) * self.bounds.height() * tongue.strength;
+                let height_pct = (y - self.bounds.min_y) / height.max(0.01); // 0 at the bottom, 1 at this tongue's tip
+                if height_pct >= 1.0 {
+                    continue;
+                }
+                // the tip wanders while the base stays put
+                let tip_offset = perlin1(t * tongue.speed * 0.7, tongue.channel + 50.0) * sway * tongue_width;
+                let axis_x = tongue.home_x + tip_offset * height_pct;
+                let side_pct = (x - axis_x).abs() / (tongue_width * (1.0 - height_pct));
+                let heat = ((1.0 - height_pct) * (1.0 - side_pct.powi(2))).max(0.0);
+                unlit *= 1.0 - heat.min(1.0) * tongue.strength;
+            }
+            led.color = heat_color((1.0 - unlit) * 1.3);
+        }
+    }
+}
@@ -1,4 +1,7 @@
-use crate::{trace_dbg, types::{Simulation, LED, RGB}};
+use crate::{
+    layout::{with_role, Bounds},
+    types::{Role, Simulation, LED, RGB},
+};
 
 // notes on candle flames:
 // - base of flame is near-transparent, slightly blue
@@ -9,18 +12,21 @@ use crate::{trace_dbg, types::{Simulation, LED, RGB}};
 #[derive(Debug)]
 pub struct CandleColoredSim {
     last_tick: u64,
+    base_leds: Vec<usize>, // the LEDs at the base of the flame, which burn blue
+    wick_leds: Vec<usize>,
+    flame_leds: Vec<usize>,
+    candle_center: f32, // the x coordinate the flame sways around
+    flame_bottom: f32, // the y coordinate the flame's colors are measured up from
 }
 
 impl CandleColoredSim {
     fn get_horiz_flicker(t: f32) -> f32 {
         // a periodic function that returns a value between -4 and 4
-        let result = 1.5 * (f32::sin(2.0 * t) + f32::sin(t) + 0.3 * f32::sin(12.0 * t) + 0.1 * f32::sin(100.0 * t));
-        result
+        1.5 * (f32::sin(2.0 * t) + f32::sin(t) + 0.3 * f32::sin(12.0 * t) + 0.1 * f32::sin(100.0 * t))
     }
     fn get_vert_flicker(t: f32) -> f32 {
         // a periodic function that returns a value between -10 and 10
-        let result = 5.0 * (0.4 * f32::sin(t) + 0.3 * f32::sin(2.0 * t) + f32::sin(3.0 * t) + 0.3 * f32::sin(8.0 * t) + 0.05 * f32::sin(130.0 * t));
-        result
+        5.0 * (0.4 * f32::sin(t) + 0.3 * f32::sin(2.0 * t) + f32::sin(3.0 * t) + 0.3 * f32::sin(8.0 * t) + 0.05 * f32::sin(130.0 * t))
     }
     
     fn hsv_to_rgb(h: f32, s: f32, v: f32) -> RGB<u8> { // a helper function to convert HSV to RGB
@@ -53,8 +59,8 @@ impl CandleColoredSim {
         let hue = (15.0 * vert_pct + 35.0).max(37.0); // move from orange to yellow up the flame
         // this one's a mess, it came from a regression. roughly, it adds a white spot ~2/3 up the flame
         let sat_unclamped = -0.247097 * f32::sin(11.8961 * vert_pct) + 0.277867 * f32::sin(8.61221 * vert_pct) + 0.5311;
-        let sat = sat_unclamped.max(0.0).min(1.0);
-        let val = if vert_pct <= 1.0 {1.0} else {(-vert_pct * 20.0 + 21.0)}; // fade out sharply if above top of flame
+        let sat = sat_unclamped.clamp(0.0, 1.0);
+        let val = if vert_pct <= 1.0 {1.0} else {-vert_pct * 20.0 + 21.0}; // fade out sharply if above top of flame
         [hue, sat, val]
     }
 }
@@ -64,9 +70,36 @@ impl Simulation for CandleColoredSim {
         "Candle flame (colored)"
     }
 
-    fn new(_leds: &[LED]) -> Self where Self: Sized {
-        // this pattern is expecting our actual layout, so we don't do fancy calculations here
-        Self { last_tick: 0 }
+    fn new(leds: &[LED]) -> Self where Self: Sized {
+        let flame_start = 0.4; // config: how far up the layout the flame's colors start, as a fraction of its height
+
+        // the parts of the candle come from the layout. any part it doesn't tag is left out, except the flame:
+        // without flame or outer LEDs, every LED that isn't the base or the wick is flame
+        let base_leds = with_role(leds, Role::Base);
+        let wick_leds = with_role(leds, Role::Wick);
+        let mut flame_leds = with_role(leds, Role::Flame);
+        flame_leds.extend(with_role(leds, Role::Outer)); // the outline is colored like the rest of the flame
+        if flame_leds.is_empty() {
+            flame_leds = (0..leds.len()).filter(|i| !base_leds.contains(i) && !wick_leds.contains(i)).collect();
+        }
+
+        // the flame sways around the candle's axis, which runs up through the wick. without a wick, go by the
+        // middle of the base, and without either, the middle of the layout
+        let bounds = Bounds::of(leds);
+        let axis_leds = if wick_leds.is_empty() { &base_leds } else { &wick_leds };
+        let candle_center = if axis_leds.is_empty() {
+            bounds.center().0
+        } else {
+            axis_leds.iter().map(|i| leds[*i].coords.0 as f32).sum::<f32>() / axis_leds.len() as f32
+        };
+        Self {
+            last_tick: 0,
+            base_leds,
+            wick_leds,
+            flame_leds,
+            candle_center,
+            flame_bottom: bounds.min_y + flame_start * bounds.height(),
+        }
     }
 
     fn tick(
@@ -75,11 +108,6 @@ impl Simulation for CandleColoredSim {
             micros: u64,
             intensity_mod: f32,
         ) {
-            let candle_base = (107, 3); // config: where the base of the candle is
-            let blue_leds = [0, 8]; // config: which LEDs are the base of the flame
-            let wick_leds = [9]; // config: which LEDs are the wick
-            let flame_leds = [1, 2, 3, 4, 5, 6, 7, 10, 11]; // config: which LEDs are the flame
-
             let blue_hue = 200.0; // config: hue of the base of the flame
 
             self.last_tick = micros;
@@ -97,11 +125,11 @@ impl Simulation for CandleColoredSim {
             // let log_str = format!("horiz flicker: {0:.2}, vert flicker: {1:.2}", horiz_flicker, vert_flicker);
             // trace_dbg!(log_str);
 
-            for wick_led in wick_leds.iter() {
+            for wick_led in self.wick_leds.iter() {
                 leds[*wick_led].color = RGB { r: 0, g: 0, b: 0 };
             }
 
-            for blue_led in blue_leds.iter() {
+            for blue_led in self.base_leds.iter() {
                 // blue leds should be more intense when the flame is vert-flickered higher or horiz-flicker to their side
                 // they should also be slightly dimmer when the intensity is lower
                 let base_brightness = 0.4;
@@ -109,24 +137,24 @@ impl Simulation for CandleColoredSim {
                 let vert_component = 0.005 * vert_flicker; // -0.05 to 0.05
                 let intensity_component = 0.03 * intensity_mod; // 0.0 to 0.03
 
-                let horiz_invert = ((leds[*blue_led].coords.0 as f32 - candle_base.0 as f32) * horiz_flicker).signum();
+                let horiz_invert = ((leds[*blue_led].coords.0 as f32 - self.candle_center) * horiz_flicker).signum();
                 
                 let brightness = base_brightness + horiz_component * horiz_invert + vert_component + intensity_component; // 0.07 to 0.36
             
                 leds[*blue_led].color = Self::hsv_to_rgb(blue_hue, 1.0, brightness);
             }
             
-            for flame_led_idx in flame_leds.iter() {
+            for flame_led_idx in self.flame_leds.iter() {
                 // for flame leds: start with base_hsv, then vary brightness based on horiz flicker
                 // no need to deal with intensity here, it's already accounted for in the flame length
 
                 let flame_led = &mut leds[*flame_led_idx];
-                let vert_pct = (flame_led.coords.1 as f32 - 14.0) / flame_height;
-                let horiz_component = 0.1 * horiz_flicker * ((flame_led.coords.0 as f32 - candle_base.0 as f32) * horiz_flicker).signum(); // -0.4 to 0.4
+                let vert_pct = (flame_led.coords.1 as f32 - self.flame_bottom) / flame_height;
+                let horiz_component = 0.1 * horiz_flicker * ((flame_led.coords.0 as f32 - self.candle_center) * horiz_flicker).signum(); // -0.4 to 0.4
 
                 let base_hsv = Self::get_flame_base_hsv(vert_pct + horiz_component);
                 let brightness_unclamped = base_hsv[2] + horiz_component;
-                let brightness = brightness_unclamped.max(0.0).min(1.0);
+                let brightness = brightness_unclamped.clamp(0.0, 1.0);
 
                 flame_led.color = Self::hsv_to_rgb(base_hsv[0], base_hsv[1], brightness);
 
@@ -134,4 +162,24 @@ impl Simulation for CandleColoredSim {
                 // trace_dbg!(log_str);
             }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_layout_without_roles_is_all_flame() {
+        let coords = [(0, 0), (4, 0), (8, 0), (2, 8), (6, 8), (4, 16)];
+        let mut leds: Vec<LED> = coords.iter().map(|coords| LED { color: RGB { r: 0, g: 0, b: 0 }, coords: *coords, role: None }).collect();
+        let mut sim = CandleColoredSim::new(&leds);
+        assert!(sim.base_leds.is_empty() && sim.wick_leds.is_empty());
+        assert_eq!(sim.flame_leds.len(), leds.len());
+        assert!((sim.candle_center - 4.0).abs() < 1e-6);
+
+        for micros in (0..3_000_000).step_by(100_000) {
+            sim.tick(&mut leds, micros, 1.0);
+            // the bottom of the flame is always lit
+            assert!(leds[..3].iter().all(|led| led.color != RGB { r: 0, g: 0, b: 0 }));
+        }
+    }
+}
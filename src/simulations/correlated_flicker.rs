@@ -0,0 +1,123 @@
+use rand::Rng;
+
+use crate::{
+    color::heat_color,
+    layout::{distance, Bounds},
+    types::{frame_dt, Simulation, LED},
+};
+
+// every LED flickers on its own, but LEDs close together flicker alike, so patches of the flame
+// brighten and dim together instead of the whole flame moving as one.
+// each LED's brightness is an Ornstein-Uhlenbeck process: it gets random kicks, and is pulled back
+// toward its usual brightness. the kicks are correlated between LEDs by how far apart they are:
+// two LEDs `d` apart have a correlation of e^(-d / correlation length). to get kicks like that, we take
+// the Cholesky factor of the correlation matrix once, then multiply independent random kicks by it every tick.
+
+#[derive(Debug)]
+pub struct CorrelatedFlickerSim {
+    last_tick: u64,
+    offsets: Vec<f32>, // how far each LED is from its usual brightness, roughly -1 to 1
+    mixing: Vec<Vec<f32>>, // lower-triangular Cholesky factor of the correlation matrix
+    base_heat: Vec<f32>, // each LED's usual heat, from a simple flame shape
+    layout: Vec<LED>, // a copy of the layout, for rebuilding `mixing` when the correlation length changes
+}
+
+impl CorrelatedFlickerSim {
+    const PRESETS: [&'static str; 3] = ["Correlated over 8 units", "Correlated over 3 units", "Correlated over 25 units"];
+
+    /// The Cholesky factor of the correlation matrix for LEDs correlated over `correlation_length` layout units.
+    fn mixing_matrix(leds: &[LED], correlation_length: f32) -> Vec<Vec<f32>> {
+        let n = leds.len();
+        let correlation = |i: usize, j: usize| {
+            let jitter = if i == j { 1e-4 } else { 0.0 }; // keeps the matrix safely positive definite for rounding errors
+            f32::exp(-distance(&leds[i], &leds[j]) / correlation_length) + jitter
+        };
+        // Cholesky-Banachiewicz: fill in the factor row by row
+        let mut factor = vec![vec![0.0; n]; n];
+        for i in 0..n {
+            for j in 0..=i {
+                let sum: f32 = (0..j).map(|k| factor[i][k] * factor[j][k]).sum();
+                factor[i][j] = if i == j {
+                    (correlation(i, i) - sum).max(1e-6).sqrt()
+                } else {
+                    (correlation(i, j) - sum) / factor[j][j]
+                };
+            }
+        }
+        factor
+    }
+
+    /// A standard normal random number, by the Box-Muller transform.
+    fn gaussian(rng: &mut impl Rng) -> f32 {
+        let u1: f32 = rng.gen_range(f32::EPSILON..1.0);
+        let u2: f32 = rng.gen();
+        (-2.0 * u1.ln()).sqrt() * f32::cos(std::f32::consts::TAU * u2)
+    }
+}
+
+impl Simulation for CorrelatedFlickerSim {
+    fn new(leds: &[LED]) -> Self where Self: Sized {
+        let bounds = Bounds::of(leds);
+        let (center_x, _) = bounds.center();
+        let base_heat = leds
+            .iter()
+            .map(|led| {
+                let height_pct = (led.coords.1 as f32 - bounds.min_y) / bounds.height(); // 0 at the bottom, 1 at the top
+                let side_pct = (led.coords.0 as f32 - center_x).abs() / (bounds.width() / 2.0); // 0 in the middle, 1 at the edges
+                0.85 - 0.45 * height_pct - 0.15 * side_pct
+            })
+            .collect();
+        Self {
+            last_tick: 0,
+            offsets: vec![0.0; leds.len()],
+            mixing: Self::mixing_matrix(leds, 8.0),
+            base_heat,
+            layout: leds.to_vec(),
+        }
+    }
+
+    fn get_name(&self) -> &str {
+        "Correlated per-LED flicker"
+    }
+
+    fn get_presets(&self) -> &'static [&'static str] {
+        &Self::PRESETS
+    }
+
+    fn apply_preset(&mut self, preset: &str) -> bool {
+        let correlation_length = match preset {
+            "Correlated over 8 units" => 8.0,
+            "Correlated over 3 units" => 3.0, // nearly independent, since the LEDs are further apart than this
+            "Correlated over 25 units" => 25.0, // most of the flame moves together
+            _ => return false,
+        };
+        self.mixing = Self::mixing_matrix(&self.layout, correlation_length);
+        true
+    }
+
+    fn tick(
+        &mut self,
+        leds: &mut Vec<LED>,
+        micros: u64,
+        intensity_mod: f32,
+    ) {
+        let pull_back: f32 = 4.0; // config: how strongly each LED is pulled back to its usual brightness, per second. higher = faster flicker
+        let spread = 0.15 + 0.2 * intensity_mod; // config: how far from usual a typical LED is, as a standard deviation
+
+        let dt = frame_dt(self.last_tick, micros, 0.1);
+        self.last_tick = micros;
+        let mut rng = rand::thread_rng();
+
+        // an OU process with pull-back θ and kick size σ settles at a standard deviation of σ / sqrt(2θ)
+        let kick_size = spread * (2.0 * pull_back).sqrt();
+        let kicks: Vec<f32> = (0..self.offsets.len()).map(|_| Self::gaussian(&mut rng)).collect();
+        for (i, offset) in self.offsets.iter_mut().enumerate() {
+            let correlated_kick: f32 = self.mixing[i].iter().zip(&kicks).map(|(weight, kick)| weight * kick).sum();
+            *offset += -pull_back * *offset * dt + kick_size * dt.sqrt() * correlated_kick;
+        }
+
+        for ((led, offset), base_heat) in leds.iter_mut().zip(&self.offsets).zip(&self.base_heat) {
+            led.color = heat_color(base_heat * (1.0 + offset));
+        }
+    }
+}
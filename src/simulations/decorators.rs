@@ -0,0 +1,258 @@
+//! Wrappers that make variations of any other simulation: slowed down, mirrored, recolored or breathing.
+//! Each one runs the wrapped simulation into its own buffer, then writes a changed copy into the real LEDs,
+//! so simulations that build on their last frame still see their own colors.
+use std::{f32::consts::TAU, time::Duration};
+
+use crate::{
+    color::{hsv_to_rgb, rgb_to_hsv, scale},
+    layout::{blank_leds, Bounds},
+    types::{Simulation, LED},
+};
+
+/// Runs the wrapped simulation faster or slower, optionally starting partway through.
+#[derive(Debug)]
+pub struct TimeWarp<S: Simulation> {
+    inner: S,
+    buffer: Vec<LED>,
+    speed: f32, // 1.0 is normal speed
+    offset: u64, // micros added to the wrapped simulation's clock
+    name: String,
+}
+
+impl<S: Simulation> TimeWarp<S> {
+    #[must_use] pub fn wrap(inner: S, leds: &[LED]) -> Self {
+        let name = inner.get_name().to_owned();
+        Self { inner, buffer: blank_leds(leds), speed: 1.0, offset: 0, name }
+    }
+
+    #[must_use] pub fn with_speed(mut self, speed: f32) -> Self {
+        self.speed = speed;
+        self.name = format!("{}, {speed}× speed", self.inner.get_name());
+        self
+    }
+
+    #[must_use] pub fn with_offset(mut self, offset: Duration) -> Self {
+        self.offset = offset.as_micros().try_into().unwrap_or(u64::MAX);
+        self
+    }
+
+    /// Convert the app's clock to the wrapped simulation's clock.
+    fn warp(&self, micros: u64) -> u64 {
+        self.offset + (micros as f64 * f64::from(self.speed)) as u64
+    }
+}
+
+impl<S: Simulation> Simulation for TimeWarp<S> {
+    fn new(leds: &[LED]) -> Self where Self: Sized {
+        Self::wrap(S::new(leds), leds)
+    }
+
+    fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    delegate_simulation!(inner => get_presets, apply_preset, is_stopped, get_status, lit_by_power_on);
+
+    fn start(&mut self, micros: u64) {
+        self.inner.start(self.warp(micros));
+    }
+
+    fn stop(&mut self, micros: u64) {
+        self.inner.stop(self.warp(micros));
+    }
+
+    fn tick(
+        &mut self,
+        leds: &mut Vec<LED>,
+        micros: u64,
+        intensity_mod: f32,
+    ) {
+        let warped_micros = self.warp(micros);
+        self.inner.tick(&mut self.buffer, warped_micros, intensity_mod);
+        for (led, warped) in leds.iter_mut().zip(&self.buffer) {
+            led.color = warped.color;
+        }
+    }
+}
+
+/// Flips the wrapped simulation left-to-right around the middle of the layout.
+#[derive(Debug)]
+pub struct Mirror<S: Simulation> {
+    inner: S,
+    buffer: Vec<LED>,
+    sources: Vec<usize>, // for each LED, which LED of the wrapped simulation lands on it
+    name: String,
+}
+
+impl<S: Simulation> Mirror<S> {
+    #[must_use] pub fn wrap(inner: S, leds: &[LED]) -> Self {
+        // the LEDs aren't perfectly symmetrical, so each LED takes its color from whichever LED
+        // is closest to its mirror image
+        let (center_x, _) = Bounds::of(leds).center();
+        let sources = leds
+            .iter()
+            .enumerate()
+            .map(|(i, led)| {
+                let mirrored_x = 2.0 * center_x - led.coords.0 as f32;
+                let distance = |other: &LED| {
+                    let dx = other.coords.0 as f32 - mirrored_x;
+                    let dy = other.coords.1 as f32 - led.coords.1 as f32;
+                    dx * dx + dy * dy
+                };
+                (0..leds.len()).min_by(|a, b| distance(&leds[*a]).total_cmp(&distance(&leds[*b]))).unwrap_or(i)
+            })
+            .collect();
+        let name = format!("{}, mirrored", inner.get_name());
+        Self { inner, buffer: blank_leds(leds), sources, name }
+    }
+}
+
+impl<S: Simulation> Simulation for Mirror<S> {
+    fn new(leds: &[LED]) -> Self where Self: Sized {
+        Self::wrap(S::new(leds), leds)
+    }
+
+    fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    delegate_simulation!(inner);
+
+    fn tick(
+        &mut self,
+        leds: &mut Vec<LED>,
+        micros: u64,
+        intensity_mod: f32,
+    ) {
+        self.inner.tick(&mut self.buffer, micros, intensity_mod);
+        for (led, source) in leds.iter_mut().zip(&self.sources) {
+            led.color = self.buffer[*source].color;
+        }
+    }
+}
+
+/// Rotates the hue of the wrapped simulation.
+#[derive(Debug)]
+pub struct HueShift<S: Simulation> {
+    inner: S,
+    buffer: Vec<LED>,
+    degrees: f32,
+    name: String,
+}
+
+impl<S: Simulation> HueShift<S> {
+    #[must_use] pub fn wrap(inner: S, leds: &[LED], degrees: f32) -> Self {
+        let name = format!("{}, hue {degrees:+}°", inner.get_name());
+        Self { inner, buffer: blank_leds(leds), degrees, name }
+    }
+}
+
+impl<S: Simulation> Simulation for HueShift<S> {
+    fn new(leds: &[LED]) -> Self where Self: Sized {
+        Self::wrap(S::new(leds), leds, 0.0)
+    }
+
+    fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    delegate_simulation!(inner);
+
+    fn tick(
+        &mut self,
+        leds: &mut Vec<LED>,
+        micros: u64,
+        intensity_mod: f32,
+    ) {
+        self.inner.tick(&mut self.buffer, micros, intensity_mod);
+        for (led, source) in leds.iter_mut().zip(&self.buffer) {
+            let [h, s, v] = rgb_to_hsv(source.color);
+            led.color = hsv_to_rgb(h + self.degrees, s, v);
+        }
+    }
+}
+
+/// Scales the saturation of the wrapped simulation. Below 1 washes colors out towards white, above 1 makes them more vivid.
+#[derive(Debug)]
+pub struct Saturation<S: Simulation> {
+    inner: S,
+    buffer: Vec<LED>,
+    factor: f32,
+    name: String,
+}
+
+impl<S: Simulation> Saturation<S> {
+    #[must_use] pub fn wrap(inner: S, leds: &[LED], factor: f32) -> Self {
+        let name = format!("{}, {:.0}% saturation", inner.get_name(), factor * 100.0);
+        Self { inner, buffer: blank_leds(leds), factor, name }
+    }
+}
+
+impl<S: Simulation> Simulation for Saturation<S> {
+    fn new(leds: &[LED]) -> Self where Self: Sized {
+        Self::wrap(S::new(leds), leds, 1.0)
+    }
+
+    fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    delegate_simulation!(inner);
+
+    fn tick(
+        &mut self,
+        leds: &mut Vec<LED>,
+        micros: u64,
+        intensity_mod: f32,
+    ) {
+        self.inner.tick(&mut self.buffer, micros, intensity_mod);
+        for (led, source) in leds.iter_mut().zip(&self.buffer) {
+            let [h, s, v] = rgb_to_hsv(source.color);
+            led.color = hsv_to_rgb(h, (s * self.factor).clamp(0.0, 1.0), v);
+        }
+    }
+}
+
+/// Slowly dims and brightens the wrapped simulation with a sine wave, like breathing.
+#[derive(Debug)]
+pub struct BrightnessLfo<S: Simulation> {
+    inner: S,
+    buffer: Vec<LED>,
+    period_secs: f32, // how long one breath takes
+    depth: f32, // 0 to 1, how far the brightness dips at the bottom of a breath
+    name: String,
+}
+
+impl<S: Simulation> BrightnessLfo<S> {
+    #[must_use] pub fn wrap(inner: S, leds: &[LED], period: Duration, depth: f32) -> Self {
+        let name = format!("{}, breathing", inner.get_name());
+        Self { inner, buffer: blank_leds(leds), period_secs: period.as_secs_f32(), depth, name }
+    }
+}
+
+impl<S: Simulation> Simulation for BrightnessLfo<S> {
+    fn new(leds: &[LED]) -> Self where Self: Sized {
+        Self::wrap(S::new(leds), leds, Duration::from_secs(4), 0.5)
+    }
+
+    fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    delegate_simulation!(inner);
+
+    fn tick(
+        &mut self,
+        leds: &mut Vec<LED>,
+        micros: u64,
+        intensity_mod: f32,
+    ) {
+        self.inner.tick(&mut self.buffer, micros, intensity_mod);
+        let t = micros as f32 / 1_000_000.0;
+        let phase = 0.5 - 0.5 * f32::cos(TAU * t / self.period_secs); // 0 to 1 to 0 over one period
+        let brightness = 1.0 - self.depth * phase;
+        for (led, source) in leds.iter_mut().zip(&self.buffer) {
+            led.color = scale(source.color, brightness);
+        }
+    }
+}
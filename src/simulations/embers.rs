@@ -0,0 +1,63 @@
+use rand::Rng;
+
+use crate::{
+    color::hsv_to_rgb,
+    noise::fbm3,
+    types::{frame_dt, Simulation, LED},
+};
+
+// a bed of coals with no flame over it: every LED slowly breathes between dark red and orange on its own,
+// and now and then one catches a bit of air and flares up for a moment.
+// the breathing comes from a noise field sampled at each LED's position, so neighboring LEDs drift together
+// the way patches of a coal bed do. everything stays dim, which is easy on the battery late at night.
+
+#[derive(Debug)]
+pub struct EmbersSim {
+    last_tick: u64,
+    flares: Vec<f32>, // per LED, how far through a flare-up it is: 0 is no flare, counting down from 1
+}
+
+impl Simulation for EmbersSim {
+    fn new(leds: &[LED]) -> Self where Self: Sized {
+        Self { last_tick: 0, flares: vec![0.0; leds.len()] }
+    }
+
+    fn get_name(&self) -> &str {
+        "Glowing embers"
+    }
+
+    fn tick(
+        &mut self,
+        leds: &mut Vec<LED>,
+        micros: u64,
+        intensity_mod: f32,
+    ) {
+        let breathe_speed = 0.15; // config: how fast the glow drifts, in noise units per second. lower is lazier
+        let spatial_scale = 0.08; // config: how big the patches that glow together are. smaller = bigger patches
+        let flares_per_second = 0.05 + 0.25 * intensity_mod; // config: how often each LED flares up, on average
+        let flare_secs = 0.6; // config: how long a flare-up lasts
+        let max_brightness = 0.35 + 0.2 * intensity_mod; // config: how bright the embers get without a flare. keep low to save power
+
+        let dt = frame_dt(self.last_tick, micros, 0.1);
+        self.last_tick = micros;
+        let t = micros as f32 / 1_000_000.0;
+        let mut rng = rand::thread_rng();
+
+        for (led, flare) in leds.iter_mut().zip(&mut self.flares) {
+            *flare = (*flare - dt / flare_secs).max(0.0);
+            if *flare == 0.0 && rng.gen::<f32>() < flares_per_second * dt {
+                *flare = 1.0;
+            }
+
+            let x = led.coords.0 as f32 * spatial_scale;
+            let y = led.coords.1 as f32 * spatial_scale;
+            let glow = (fbm3(x, y, t * breathe_speed, 2) * 1.5 + 0.5).clamp(0.0, 1.0); // 0 is barely lit, 1 is glowing orange
+            // flare-ups jump up quickly and then fade: the countdown squared spends most of its time near the end
+            let flare_glow = flare.powi(2);
+
+            let hue = 2.0 + 20.0 * glow + 15.0 * flare_glow; // deep red to orange, and yellower while flaring
+            let val = (0.04 + (max_brightness - 0.04) * glow).max(flare_glow * 0.9);
+            led.color = hsv_to_rgb(hue, 1.0, val);
+        }
+    }
+}
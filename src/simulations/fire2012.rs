@@ -0,0 +1,126 @@
+use rand::Rng;
+
+use crate::{layout::Bounds, types::{Simulation, LED, RGB}};
+
+// a port of FastLED's classic Fire2012 (https://github.com/FastLED/FastLED/blob/master/examples/Fire2012/Fire2012.ino).
+// Fire2012 runs on a 1D strip of heat cells. we lay the strip along the vertical axis of the layout
+// and sample it at each LED's height, so it doesn't matter how the LEDs are arranged.
+
+const NUM_CELLS: usize = 20; // how many heat cells the strip has, bottom to top
+const STEPS_PER_SECOND: f32 = 60.0; // Fire2012 is tuned for running once per frame at about 60fps
+
+#[derive(Debug)]
+pub struct Fire2012Sim {
+    last_tick: u64,
+    unsimulated_secs: f32, // time we haven't run a step for yet
+    heat: [u8; NUM_CELLS], // bottom cell first
+    bounds: Bounds,
+    cooling: u8, // how much the air cools as it rises. more cooling = shorter flames. FastLED suggests 20-100
+    sparking: u8, // chance (out of 255) of a new spark each step. more sparking = roarier fire. FastLED suggests 50-200
+}
+
+impl Fire2012Sim {
+    const PRESETS: [&'static str; 3] = ["Default", "Tall", "Gentle"];
+
+    /// Run one step of the Fire2012 algorithm.
+    fn step(&mut self, intensity_mod: f32) {
+        let mut rng = rand::thread_rng();
+
+        // step 1: cool down every cell a little
+        let max_cooling = (u32::from(self.cooling) * 10 / NUM_CELLS as u32 + 2) as u8;
+        for cell in &mut self.heat {
+            *cell = cell.saturating_sub(rng.gen_range(0..=max_cooling));
+        }
+
+        // step 2: heat from each cell drifts up and diffuses a little
+        for k in (2..NUM_CELLS).rev() {
+            self.heat[k] = ((u16::from(self.heat[k - 1]) + 2 * u16::from(self.heat[k - 2])) / 3) as u8;
+        }
+
+        // step 3: randomly ignite new sparks near the bottom. intensity controls how often
+        let sparking = f32::from(self.sparking) * intensity_mod;
+        if f32::from(rng.gen::<u8>()) < sparking {
+            let spark_cells = NUM_CELLS / 3; // Fire2012 sparks in the bottom 7 of 60 LEDs; we have fewer, bigger cells
+            let y = rng.gen_range(0..spark_cells);
+            self.heat[y] = self.heat[y].saturating_add(rng.gen_range(160..=255));
+        }
+    }
+
+    /// Sample the heat at a height between 0 (bottom cell) and 1 (top cell), interpolating between cells.
+    fn heat_at(&self, height_pct: f32) -> f32 {
+        let position = height_pct.clamp(0.0, 1.0) * (NUM_CELLS - 1) as f32;
+        let below = position.floor() as usize;
+        let above = (below + 1).min(NUM_CELLS - 1);
+        let frac = position - below as f32;
+        f32::from(self.heat[below]) * (1.0 - frac) + f32::from(self.heat[above]) * frac
+    }
+
+    /// FastLED's `HeatColor`: black to red to yellow to white.
+    fn heat_color(temperature: u8) -> RGB<u8> {
+        let t192 = ((u16::from(temperature) * 192) >> 8) as u8; // scale to 0-191
+        let heat_ramp = (t192 & 0x3F) << 2; // 0-252 within each third
+        if t192 & 0x80 != 0 {
+            RGB { r: 255, g: 255, b: heat_ramp } // hottest
+        } else if t192 & 0x40 != 0 {
+            RGB { r: 255, g: heat_ramp, b: 0 } // middle
+        } else {
+            RGB { r: heat_ramp, g: 0, b: 0 } // coolest
+        }
+    }
+}
+
+impl Simulation for Fire2012Sim {
+    fn new(leds: &[LED]) -> Self where Self: Sized {
+        Self {
+            last_tick: 0,
+            unsimulated_secs: 0.0,
+            heat: [0; NUM_CELLS],
+            bounds: Bounds::of(leds),
+            cooling: 55,
+            sparking: 120,
+        }
+    }
+
+    fn get_name(&self) -> &str {
+        "Fire2012 heat cells"
+    }
+
+    fn get_presets(&self) -> &'static [&'static str] {
+        &Self::PRESETS
+    }
+
+    fn apply_preset(&mut self, preset: &str) -> bool {
+        let (cooling, sparking) = match preset {
+            "Default" => (55, 120), // FastLED's defaults
+            "Tall" => (35, 170),
+            "Gentle" => (85, 60),
+            _ => return false,
+        };
+        self.cooling = cooling;
+        self.sparking = sparking;
+        true
+    }
+
+    fn tick(
+        &mut self,
+        leds: &mut Vec<LED>,
+        micros: u64,
+        intensity_mod: f32,
+    ) {
+        let dt = micros.saturating_sub(self.last_tick) as f32 / 1_000_000.0;
+        self.last_tick = micros;
+
+        // run however many fixed-size steps have built up, so the fire looks the same at any frame rate.
+        // cap it so coming back after a long time doesn't stall
+        self.unsimulated_secs = (self.unsimulated_secs + dt).min(1.0);
+        while self.unsimulated_secs >= 1.0 / STEPS_PER_SECOND {
+            self.step(intensity_mod);
+            self.unsimulated_secs -= 1.0 / STEPS_PER_SECOND;
+        }
+
+        for led in leds.iter_mut() {
+            let height_pct = (led.coords.1 as f32 - self.bounds.min_y) / self.bounds.height();
+            led.color = Self::heat_color(self.heat_at(height_pct) as u8);
+        }
+    }
+}
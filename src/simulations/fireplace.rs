@@ -0,0 +1,112 @@
+use crate::{
+    color::{heat_color, hsv_to_rgb},
+    layout::{with_role, Bounds},
+    noise::perlin1,
+    types::{frame_dt, Role, Simulation, LED},
+};
+
+// a log fire in a fireplace. the lowest LEDs are a bed of coals that glows steadily, and the LEDs above
+// them are flames that each lick up and down quickly on their own.
+// the fire burns down over several minutes: roaring with tall flames, then settling as the flames
+// shrink and the coals build up, then down to embers with hardly any flame left.
+
+#[derive(Debug)]
+pub struct FireplaceSim {
+    last_tick: u64,
+    stage: f32, // how burnt down the log is: 0 is roaring, 0.5 is settling, 1 is embers
+    is_coal: Vec<bool>, // whether each LED is part of the coal bed
+    bounds: Bounds,
+}
+
+impl FireplaceSim {
+    const PRESETS: [&'static str; 3] = ["Fresh log", "Settling", "Embers"];
+
+    /// `roaring`, `settling` or `embers`, blended by where `stage` falls between them.
+    fn by_stage(stage: f32, roaring: f32, settling: f32, embers: f32) -> f32 {
+        if stage < 0.5 {
+            roaring + (settling - roaring) * stage * 2.0
+        } else {
+            settling + (embers - settling) * (stage - 0.5) * 2.0
+        }
+    }
+}
+
+impl Simulation for FireplaceSim {
+    fn new(leds: &[LED]) -> Self where Self: Sized {
+        let coal_height = 0.2; // config: LEDs in this fraction of the layout height (from the bottom) are the coal bed
+        let bounds = Bounds::of(leds);
+        // the lowest LEDs are the coal bed. LEDs the layout tags as the base of the candle sit at the bottom too,
+        // so they join it even if the board puts them a little higher
+        let base_leds = with_role(leds, Role::Base);
+        let is_coal = leds
+            .iter()
+            .enumerate()
+            .map(|(i, led)| led.coords.1 as f32 <= bounds.min_y + coal_height * bounds.height() || base_leds.contains(&i))
+            .collect();
+        Self { last_tick: 0, stage: 0.0, is_coal, bounds }
+    }
+
+    fn get_name(&self) -> &str {
+        "Fireplace"
+    }
+
+    fn get_presets(&self) -> &'static [&'static str] {
+        &Self::PRESETS
+    }
+
+    fn apply_preset(&mut self, preset: &str) -> bool {
+        self.stage = match preset {
+            "Fresh log" => 0.0,
+            "Settling" => 0.5,
+            "Embers" => 1.0,
+            _ => return false,
+        };
+        true
+    }
+
+    fn get_status(&self) -> Option<String> {
+        let name = if self.stage < 0.25 {
+            "roaring"
+        } else if self.stage < 0.75 {
+            "settling"
+        } else {
+            "embers"
+        };
+        Some(format!("Log is {name} ({:.0}% burnt)", self.stage * 100.0))
+    }
+
+    fn tick(
+        &mut self,
+        leds: &mut Vec<LED>,
+        micros: u64,
+        intensity_mod: f32,
+    ) {
+        let burn_minutes = 12.0; // config: how long a log takes to go from roaring to embers
+        let flame_speed = 2.5; // config: how fast the flames lick up and down, in noise units per second
+
+        let dt = frame_dt(self.last_tick, micros, 0.1);
+        self.last_tick = micros;
+        let t = micros as f32 / 1_000_000.0;
+        // a hotter fire burns through the log faster
+        self.stage = (self.stage + dt / (burn_minutes * 60.0) * (0.5 + intensity_mod)).min(1.0);
+
+        let flame_height = Self::by_stage(self.stage, 1.4, 0.9, 0.35); // as a fraction of the layout height
+        let coal_glow = Self::by_stage(self.stage, 0.7, 1.0, 0.45); // the coals are brightest once the log has settled
+        let speed = flame_speed * Self::by_stage(self.stage, 1.3, 1.0, 0.6);
+
+        for (i, led) in leds.iter_mut().enumerate() {
+            let height_pct = (led.coords.1 as f32 - self.bounds.min_y) / self.bounds.height(); // 0 at the bottom, 1 at the top
+            if self.is_coal[i] {
+                // the coals glow steadily, just shifting slowly between red and orange
+                let shimmer = perlin1(t * 0.3, i as f32);
+                let hue = 8.0 + 10.0 * coal_glow + 8.0 * shimmer;
+                led.color = hsv_to_rgb(hue, 1.0, (coal_glow * (0.85 + 0.3 * shimmer)).clamp(0.0, 1.0));
+            } else {
+                // each flame LED licks up and down on its own, quickly, with an extra fast flutter
+                let lick = perlin1(t * speed, i as f32 * 3.1) + 0.4 * perlin1(t * speed * 3.0, i as f32 * 3.1 + 50.0);
+                let reach = flame_height * (1.0 + 0.6 * lick);
+                led.color = heat_color((1.0 - height_pct / reach.max(0.01)) * 1.3);
+            }
+        }
+    }
+}
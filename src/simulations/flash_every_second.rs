@@ -15,6 +15,10 @@ impl Simulation for FlashEverySecondSim {
         "Flash every second"
     }
 
+    fn lit_by_power_on(&self) -> bool {
+        false // a test pattern, not a flame
+    }
+
     fn tick(
         &mut self,
         leds: &mut Vec<LED>,
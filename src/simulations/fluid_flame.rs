@@ -0,0 +1,329 @@
+use std::{mem::swap, time::Instant};
+
+use rand::Rng;
+
+use crate::{
+    color::heat_color,
+    layout::Bounds,
+    types::{Simulation, LED},
+};
+
+// a small version of Jos Stam's "Real-Time Fluid Dynamics for Games" (stable fluids).
+// a grid of cells covers the layout's bounding box. every step, heat is added at the wick,
+// hot cells push the air above them upward (buoyancy), and the air carries the heat along with it.
+// each LED shows the temperature of the air at its position.
+// everything is in grid units: positions are in cells, velocities are in cells per second.
+
+const STEPS_PER_SECOND: f32 = 30.0; // the solver runs at a fixed rate so it behaves the same at any frame rate
+
+// config: rough floating point throughput of the board's microcontroller. the performance report compares
+// against this. a Cortex-M0+ with no FPU (like the RP2040) manages something like 1-2 million float ops per
+// second in software
+const BOARD_FLOPS: f32 = 1.5e6;
+const BOARD_FRAME_BUDGET_MS: f32 = 1000.0 / 30.0; // config: how long the board has to draw a frame
+const BOARD_RAM_BUDGET_BYTES: usize = 32 * 1024; // config: how much RAM the board could spare for the solver
+
+#[derive(Clone, Copy, Debug)]
+struct Grid {
+    nx: usize, // cells across, not counting the boundary cells
+    ny: usize, // cells up, not counting the boundary cells
+}
+
+impl Grid {
+    const fn len(self) -> usize {
+        (self.nx + 2) * (self.ny + 2)
+    }
+
+    const fn ix(self, i: usize, j: usize) -> usize {
+        i + (self.nx + 2) * j
+    }
+
+    /// Fill in the boundary cells. `b` is 1 for horizontal velocity and 2 for vertical velocity, so the walls
+    /// reflect flow hitting them; anything else is just copied outward.
+    fn set_bounds(self, b: u8, x: &mut [f32]) {
+        let (nx, ny) = (self.nx, self.ny);
+        for i in 1..=nx {
+            x[self.ix(i, 0)] = if b == 2 { -x[self.ix(i, 1)] } else { x[self.ix(i, 1)] };
+            // the top is open, so hot air can leave instead of piling up against a lid
+            x[self.ix(i, ny + 1)] = x[self.ix(i, ny)];
+        }
+        for j in 1..=ny {
+            x[self.ix(0, j)] = if b == 1 { -x[self.ix(1, j)] } else { x[self.ix(1, j)] };
+            x[self.ix(nx + 1, j)] = if b == 1 { -x[self.ix(nx, j)] } else { x[self.ix(nx, j)] };
+        }
+        x[self.ix(0, 0)] = 0.5 * (x[self.ix(1, 0)] + x[self.ix(0, 1)]);
+        x[self.ix(0, ny + 1)] = 0.5 * (x[self.ix(1, ny + 1)] + x[self.ix(0, ny)]);
+        x[self.ix(nx + 1, 0)] = 0.5 * (x[self.ix(nx, 0)] + x[self.ix(nx + 1, 1)]);
+        x[self.ix(nx + 1, ny + 1)] = 0.5 * (x[self.ix(nx, ny + 1)] + x[self.ix(nx + 1, ny)]);
+    }
+
+    /// Gauss-Seidel relaxation, used by both diffusion and projection.
+    fn lin_solve(self, b: u8, x: &mut [f32], x0: &[f32], a: f32, c: f32, iterations: usize) {
+        for _ in 0..iterations {
+            for j in 1..=self.ny {
+                for i in 1..=self.nx {
+                    let neighbors = x[self.ix(i - 1, j)] + x[self.ix(i + 1, j)] + x[self.ix(i, j - 1)] + x[self.ix(i, j + 1)];
+                    x[self.ix(i, j)] = (x0[self.ix(i, j)] + a * neighbors) / c;
+                }
+            }
+            self.set_bounds(b, x);
+        }
+    }
+
+    fn diffuse(self, b: u8, x: &mut [f32], x0: &[f32], rate: f32, dt: f32, iterations: usize) {
+        let a = dt * rate;
+        self.lin_solve(b, x, x0, a, 1.0 + 4.0 * a, iterations);
+    }
+
+    /// Move `d0` along the velocity field (`u`, `v`) by tracing each cell backwards in time.
+    fn advect(self, b: u8, d: &mut [f32], d0: &[f32], u: &[f32], v: &[f32], dt: f32) {
+        for j in 1..=self.ny {
+            for i in 1..=self.nx {
+                let x = (i as f32 - dt * u[self.ix(i, j)]).clamp(0.5, self.nx as f32 + 0.5);
+                let y = (j as f32 - dt * v[self.ix(i, j)]).clamp(0.5, self.ny as f32 + 0.5);
+                let (i0, j0) = (x.floor() as usize, y.floor() as usize);
+                let (s1, t1) = (x - i0 as f32, y - j0 as f32);
+                let (s0, t0) = (1.0 - s1, 1.0 - t1);
+                d[self.ix(i, j)] = s0 * (t0 * d0[self.ix(i0, j0)] + t1 * d0[self.ix(i0, j0 + 1)])
+                    + s1 * (t0 * d0[self.ix(i0 + 1, j0)] + t1 * d0[self.ix(i0 + 1, j0 + 1)]);
+            }
+        }
+        self.set_bounds(b, d);
+    }
+
+    /// Make the velocity field mass-conserving, which is what gives the flow its swirls.
+    fn project(self, u: &mut [f32], v: &mut [f32], p: &mut [f32], div: &mut [f32], iterations: usize) {
+        for j in 1..=self.ny {
+            for i in 1..=self.nx {
+                div[self.ix(i, j)] = -0.5 * (u[self.ix(i + 1, j)] - u[self.ix(i - 1, j)] + v[self.ix(i, j + 1)] - v[self.ix(i, j - 1)]);
+                p[self.ix(i, j)] = 0.0;
+            }
+        }
+        self.set_bounds(0, div);
+        self.set_bounds(0, p);
+        self.lin_solve(0, p, div, 1.0, 4.0, iterations);
+        for j in 1..=self.ny {
+            for i in 1..=self.nx {
+                u[self.ix(i, j)] -= 0.5 * (p[self.ix(i + 1, j)] - p[self.ix(i - 1, j)]);
+                v[self.ix(i, j)] -= 0.5 * (p[self.ix(i, j + 1)] - p[self.ix(i, j - 1)]);
+            }
+        }
+        self.set_bounds(1, u);
+        self.set_bounds(2, v);
+    }
+
+    /// Bilinearly sample `field` at a position in cells.
+    fn sample(self, field: &[f32], x: f32, y: f32) -> f32 {
+        let x = x.clamp(0.0, self.nx as f32 + 1.0 - 1e-3);
+        let y = y.clamp(0.0, self.ny as f32 + 1.0 - 1e-3);
+        let (i0, j0) = (x.floor() as usize, y.floor() as usize);
+        let (s1, t1) = (x - i0 as f32, y - j0 as f32);
+        let (s0, t0) = (1.0 - s1, 1.0 - t1);
+        s0 * (t0 * field[self.ix(i0, j0)] + t1 * field[self.ix(i0, j0 + 1)])
+            + s1 * (t0 * field[self.ix(i0 + 1, j0)] + t1 * field[self.ix(i0 + 1, j0 + 1)])
+    }
+}
+
+#[derive(Debug)]
+pub struct FluidFlameSim {
+    last_tick: u64,
+    unsimulated_secs: f32,
+    grid: Grid,
+    iterations: usize, // Gauss-Seidel iterations per solve. fewer is faster but the flow gets mushier
+    origin: (f32, f32), // layout position of the bottom-left corner of cell (0, 0)
+    cell_size: f32, // layout units per cell
+    wick: (f32, f32), // where heat comes in, in cells
+    u: Vec<f32>,
+    v: Vec<f32>,
+    u0: Vec<f32>,
+    v0: Vec<f32>,
+    temp: Vec<f32>,
+    temp0: Vec<f32>,
+    bounds: Bounds,
+    performance_mode: bool, // measure the solver and report whether it could run on the board
+    step_ms: f32, // smoothed time one step takes on this machine
+    name: &'static str,
+}
+
+impl FluidFlameSim {
+    const PRESETS: [&'static str; 2] = ["Full resolution", "Board-sized"];
+
+    /// The same simulation, but it measures itself and reports whether it could run on the board.
+    #[must_use] pub fn with_performance_report(mut self) -> Self {
+        self.performance_mode = true;
+        self.name = "Fluid flame (performance mode)";
+        self
+    }
+
+    /// Set up the grid with `nx` cells across, and as many square cells up as it takes to cover the layout.
+    fn resize(&mut self, nx: usize, iterations: usize) {
+        let padding = 3.0; // config: layout units of air around the LEDs, so the walls aren't right on top of them
+        let width = self.bounds.width() + 2.0 * padding;
+        let height = self.bounds.height() + 2.0 * padding;
+        self.cell_size = width / nx as f32;
+        let ny = (height / self.cell_size).ceil() as usize;
+        self.grid = Grid { nx, ny };
+        self.iterations = iterations;
+        // the boundary cells are at index 0, so cell i covers layout x from origin + (i - 0.5) * cell_size
+        self.origin = (self.bounds.min_x - padding - 0.5 * self.cell_size, self.bounds.min_y - padding - 0.5 * self.cell_size);
+        let (center_x, _) = self.bounds.center();
+        self.wick = ((center_x - self.origin.0) / self.cell_size, (self.bounds.min_y - self.origin.1) / self.cell_size);
+        for field in [&mut self.u, &mut self.v, &mut self.u0, &mut self.v0, &mut self.temp, &mut self.temp0] {
+            *field = vec![0.0; self.grid.len()];
+        }
+    }
+
+    fn step(&mut self, dt: f32, intensity_mod: f32) {
+        let heat_rate = 6.0 + 8.0 * intensity_mod; // config: heat per second added to each wick cell
+        let buoyancy = 6.0 + 10.0 * intensity_mod; // config: how hard hot air is pushed up, cells/s² per unit of heat
+        let jitter = 400.0; // config: random sideways pushes at the wick, cells/s², so the flame doesn't stand perfectly still
+        let cooling = 1.2; // config: how fast the air loses heat, per second
+        let viscosity = 0.2; // config: how thick the air is, cells²/s
+        let heat_diffusion = 1.5; // config: how fast heat spreads out on its own, cells²/s
+        let wick_half_width = (self.bounds.width() * 0.2 / self.cell_size).round().max(1.0) as usize; // config: how wide the wick is
+
+        let grid = self.grid;
+        let iterations = self.iterations;
+        let mut rng = rand::thread_rng();
+
+        // forces and sources: heat and sideways jitter come in around the wick, hot air rises
+        let wick_i = self.wick.0.round() as usize;
+        let wick_j = (self.wick.1.round() as usize).max(1);
+        for j in wick_j..=(wick_j + 1).min(grid.ny) {
+            for i in wick_i.saturating_sub(wick_half_width).max(1)..=(wick_i + wick_half_width).min(grid.nx) {
+                self.temp[grid.ix(i, j)] += heat_rate * rng.gen_range(0.5..1.5) * dt;
+            }
+        }
+        let sway = rng.gen_range(-1.0..1.0) * jitter * dt;
+        self.u[grid.ix(wick_i.clamp(1, grid.nx), (wick_j + 2).min(grid.ny))] += sway;
+        for idx in 0..grid.len() {
+            self.v[idx] += buoyancy * self.temp[idx] * dt;
+        }
+
+        // velocity step
+        swap(&mut self.u0, &mut self.u);
+        grid.diffuse(1, &mut self.u, &self.u0, viscosity, dt, iterations);
+        swap(&mut self.v0, &mut self.v);
+        grid.diffuse(2, &mut self.v, &self.v0, viscosity, dt, iterations);
+        grid.project(&mut self.u, &mut self.v, &mut self.u0, &mut self.v0, iterations);
+        swap(&mut self.u0, &mut self.u);
+        swap(&mut self.v0, &mut self.v);
+        grid.advect(1, &mut self.u, &self.u0, &self.u0, &self.v0, dt);
+        grid.advect(2, &mut self.v, &self.v0, &self.u0, &self.v0, dt);
+        grid.project(&mut self.u, &mut self.v, &mut self.u0, &mut self.v0, iterations);
+
+        // temperature step
+        swap(&mut self.temp0, &mut self.temp);
+        grid.diffuse(0, &mut self.temp, &self.temp0, heat_diffusion, dt, iterations);
+        swap(&mut self.temp0, &mut self.temp);
+        grid.advect(0, &mut self.temp, &self.temp0, &self.u, &self.v, dt);
+        let cooling_factor = f32::exp(-cooling * dt);
+        for temp in &mut self.temp {
+            *temp *= cooling_factor;
+        }
+    }
+
+    /// A rough count of the float operations in one step, from the loops in `step`.
+    fn flops_per_step(&self) -> usize {
+        let cells = self.grid.nx * self.grid.ny;
+        let solves = 5; // 3 diffusions and 2 projections
+        let solve_flops = solves * self.iterations * cells * 7;
+        let advect_flops = 3 * cells * 22;
+        let project_flops = 2 * cells * 12;
+        let force_flops = cells * 4;
+        solve_flops + advect_flops + project_flops + force_flops
+    }
+}
+
+impl Simulation for FluidFlameSim {
+    fn new(leds: &[LED]) -> Self where Self: Sized {
+        let mut sim = Self {
+            last_tick: 0,
+            unsimulated_secs: 0.0,
+            grid: Grid { nx: 0, ny: 0 },
+            iterations: 0,
+            origin: (0.0, 0.0),
+            cell_size: 1.0,
+            wick: (0.0, 0.0),
+            u: vec![],
+            v: vec![],
+            u0: vec![],
+            v0: vec![],
+            temp: vec![],
+            temp0: vec![],
+            bounds: Bounds::of(leds),
+            performance_mode: false,
+            step_ms: 0.0,
+            name: "Fluid flame",
+        };
+        sim.resize(16, 20);
+        sim
+    }
+
+    fn get_name(&self) -> &str {
+        self.name
+    }
+
+    fn get_presets(&self) -> &'static [&'static str] {
+        &Self::PRESETS
+    }
+
+    fn apply_preset(&mut self, preset: &str) -> bool {
+        match preset {
+            "Full resolution" => self.resize(16, 20),
+            "Board-sized" => self.resize(8, 6),
+            _ => return false,
+        }
+        true
+    }
+
+    fn get_status(&self) -> Option<String> {
+        if !self.performance_mode {
+            return None;
+        }
+        let flops = self.flops_per_step();
+        let board_ms = flops as f32 / BOARD_FLOPS * 1000.0;
+        let ram_bytes = 6 * self.grid.len() * size_of::<f32>();
+        let plausible = board_ms <= BOARD_FRAME_BUDGET_MS && ram_bytes <= BOARD_RAM_BUDGET_BYTES;
+        Some(format!(
+            "{}×{} grid, {} iterations: {:.2} ms/step here, ~{}k flops/step, est. {:.0} ms/step and {:.1} KiB on the board: {}",
+            self.grid.nx,
+            self.grid.ny,
+            self.iterations,
+            self.step_ms,
+            flops / 1000,
+            board_ms,
+            ram_bytes as f32 / 1024.0,
+            if plausible { "could plausibly run" } else { "too heavy for the board" },
+        ))
+    }
+
+    fn tick(
+        &mut self,
+        leds: &mut Vec<LED>,
+        micros: u64,
+        intensity_mod: f32,
+    ) {
+        let dt = micros.saturating_sub(self.last_tick) as f32 / 1_000_000.0;
+        self.last_tick = micros;
+
+        // run however many fixed-size steps have built up, capped so coming back after a long time doesn't stall
+        self.unsimulated_secs = (self.unsimulated_secs + dt).min(0.5);
+        while self.unsimulated_secs >= 1.0 / STEPS_PER_SECOND {
+            let started = Instant::now();
+            self.step(1.0 / STEPS_PER_SECOND, intensity_mod);
+            if self.performance_mode {
+                let step_ms = started.elapsed().as_secs_f32() * 1000.0;
+                self.step_ms = if self.step_ms == 0.0 { step_ms } else { 0.9 * self.step_ms + 0.1 * step_ms };
+            }
+            self.unsimulated_secs -= 1.0 / STEPS_PER_SECOND;
+        }
+
+        let heat_scale = 0.45; // config: how much temperature it takes to look white-hot
+        for led in leds.iter_mut() {
+            let x = (led.coords.0 as f32 - self.origin.0) / self.cell_size;
+            let y = (led.coords.1 as f32 - self.origin.1) / self.cell_size;
+            led.color = heat_color(self.grid.sample(&self.temp, x, y) * heat_scale);
+        }
+    }
+}
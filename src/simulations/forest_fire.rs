@@ -0,0 +1,132 @@
+use rand::Rng;
+
+use crate::{
+    color::{hsv_to_rgb, mix},
+    layout::NeighborGraph,
+    types::{frame_dt, Simulation, LED, RGB},
+};
+
+// not a realistic flame, just for fun: the Drossel-Schwabl forest-fire model, with every LED a patch of forest.
+// each step, a burning patch burns out to ash, a tree next to a burning patch may catch fire, lightning
+// now and then sets a tree alight on its own, and ash slowly grows back into trees.
+// "next to" comes from the layout: each LED's nearest few LEDs are its neighbors.
+
+const STEPS_PER_SECOND: f32 = 4.0; // config: how fast the automaton runs
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Cell {
+    Tree,
+    Burning,
+    Ash { steps: u8 }, // how many steps ago it burnt out
+}
+
+#[derive(Debug)]
+pub struct ForestFireSim {
+    last_tick: u64,
+    unsimulated_secs: f32,
+    cells: Vec<Cell>,
+    graph: NeighborGraph,
+    spread_chance: f32, // chance per step that a tree next to a fire catches
+    lightning_chance: f32, // chance per step that any one tree gets struck, at full intensity
+    regrowth_chance: f32, // chance per step that ash grows back into a tree
+    tree_color: RGB<u8>,
+    burning_hues: (f32, f32), // the range of hues burning patches flicker between
+    ember_color: RGB<u8>, // the glow of a patch that has just burnt out
+    ash_color: RGB<u8>,
+}
+
+impl ForestFireSim {
+    const PRESETS: [&'static str; 3] = ["Default", "Dry season", "Rainforest"];
+
+    fn step(&mut self, intensity_mod: f32, rng: &mut impl Rng) {
+        let lightning_chance = self.lightning_chance * intensity_mod;
+        let next: Vec<Cell> = (0..self.cells.len())
+            .map(|i| match self.cells[i] {
+                Cell::Burning => Cell::Ash { steps: 0 },
+                Cell::Ash { steps } => if rng.gen::<f32>() < self.regrowth_chance { Cell::Tree } else { Cell::Ash { steps: steps.saturating_add(1) } },
+                Cell::Tree => {
+                    let near_fire = self.graph.neighbors(i).iter().any(|(j, _)| self.cells[*j] == Cell::Burning);
+                    let caught = near_fire && rng.gen::<f32>() < self.spread_chance;
+                    if caught || rng.gen::<f32>() < lightning_chance { Cell::Burning } else { Cell::Tree }
+                }
+            })
+            .collect();
+        self.cells = next;
+    }
+}
+
+impl Simulation for ForestFireSim {
+    fn new(leds: &[LED]) -> Self where Self: Sized {
+        Self {
+            last_tick: 0,
+            unsimulated_secs: 0.0,
+            cells: vec![Cell::Tree; leds.len()],
+            graph: NeighborGraph::k_nearest(leds, 3),
+            spread_chance: 0.7,
+            lightning_chance: 0.01,
+            regrowth_chance: 0.04,
+            tree_color: RGB { r: 10, g: 70, b: 5 },
+            burning_hues: (20.0, 45.0),
+            ember_color: RGB { r: 120, g: 25, b: 0 },
+            ash_color: RGB { r: 12, g: 8, b: 8 },
+        }
+    }
+
+    fn get_name(&self) -> &str {
+        "Forest fire automaton"
+    }
+
+    fn get_presets(&self) -> &'static [&'static str] {
+        &Self::PRESETS
+    }
+
+    fn apply_preset(&mut self, preset: &str) -> bool {
+        (
+            self.spread_chance,
+            self.lightning_chance,
+            self.regrowth_chance,
+            self.tree_color,
+            self.burning_hues,
+            self.ember_color,
+            self.ash_color,
+        ) = match preset {
+            "Default" => (0.7, 0.01, 0.04, RGB { r: 10, g: 70, b: 5 }, (20.0, 45.0), RGB { r: 120, g: 25, b: 0 }, RGB { r: 12, g: 8, b: 8 }),
+            // fires sweep through everything, burning hot and leaving pale ash
+            "Dry season" => (0.95, 0.03, 0.02, RGB { r: 60, g: 55, b: 5 }, (30.0, 55.0), RGB { r: 160, g: 50, b: 0 }, RGB { r: 25, g: 22, b: 20 }),
+            // fires stay small, smoulder red and grow back fast
+            "Rainforest" => (0.35, 0.01, 0.15, RGB { r: 0, g: 90, b: 20 }, (5.0, 30.0), RGB { r: 90, g: 10, b: 0 }, RGB { r: 6, g: 8, b: 5 }),
+            _ => return false,
+        };
+        true
+    }
+
+    fn tick(
+        &mut self,
+        leds: &mut Vec<LED>,
+        micros: u64,
+        intensity_mod: f32,
+    ) {
+        let dt = frame_dt(self.last_tick, micros, 1.0); // don't run hundreds of steps after a pause
+        self.last_tick = micros;
+        let mut rng = rand::thread_rng();
+
+        self.unsimulated_secs += dt;
+        while self.unsimulated_secs >= 1.0 / STEPS_PER_SECOND {
+            self.step(intensity_mod, &mut rng);
+            self.unsimulated_secs -= 1.0 / STEPS_PER_SECOND;
+        }
+
+        // fade between steps so the fire creeps instead of blinking from LED to LED
+        let between_steps = self.unsimulated_secs * STEPS_PER_SECOND;
+        for (led, cell) in leds.iter_mut().zip(&self.cells) {
+            led.color = match cell {
+                Cell::Tree => self.tree_color,
+                // flames flicker between orange and yellow, and die down toward the end of the step
+                Cell::Burning => hsv_to_rgb(rng.gen_range(self.burning_hues.0..self.burning_hues.1), 1.0, 1.0 - 0.5 * between_steps),
+                // still glowing just after it burnt out
+                Cell::Ash { steps: 0 } => mix(self.ember_color, self.ash_color, between_steps.sqrt()),
+                Cell::Ash { .. } => self.ash_color,
+            };
+        }
+    }
+}
@@ -0,0 +1,91 @@
+use crate::{
+    color::{hsv_to_rgb, mix},
+    layout::Bounds,
+    noise::perlin1,
+    types::{Simulation, LED},
+};
+
+// a gas flame burns very differently from a candle: with enough air mixed in, the gas burns completely
+// and the flame is a steady blue cone with a brighter, paler cone inside it, barely flickering at all.
+// starve it of air and it can't burn everything at the base, so soot glows yellow at the tips and the
+// flame gets taller and lazier. `air_mix` goes from 0 (no air, a yellow flame) to 1 (fully aerated, all blue).
+
+#[derive(Debug)]
+pub struct GasBurnerSim {
+    air_mix: f32,
+    bounds: Bounds,
+}
+
+impl GasBurnerSim {
+    const PRESETS: [&'static str; 3] = ["Well aerated", "Slightly rich", "Starved of air"];
+}
+
+impl Simulation for GasBurnerSim {
+    fn new(leds: &[LED]) -> Self where Self: Sized {
+        Self { air_mix: 1.0, bounds: Bounds::of(leds) }
+    }
+
+    fn get_name(&self) -> &str {
+        "Gas burner"
+    }
+
+    fn get_presets(&self) -> &'static [&'static str] {
+        &Self::PRESETS
+    }
+
+    fn apply_preset(&mut self, preset: &str) -> bool {
+        self.air_mix = match preset {
+            "Well aerated" => 1.0,
+            "Slightly rich" => 0.6,
+            "Starved of air" => 0.15,
+            _ => return false,
+        };
+        true
+    }
+
+    fn tick(
+        &mut self,
+        leds: &mut Vec<LED>,
+        micros: u64,
+        intensity_mod: f32,
+    ) {
+        let cone_hue = 225.0; // config: hue of the outer blue cone
+        let core_hue = 195.0; // config: hue of the bright inner cone
+        let soot_hue = 38.0; // config: hue of the yellow tips when starved of air
+        let core_fraction = 0.45; // config: how tall the inner cone is compared to the outer one
+        let flicker_speed = 25.0; // config: how fast the flame shimmers, in noise units per second
+        let flicker_amount = 0.03 + 0.12 * (1.0 - self.air_mix); // config: how much it shimmers. rich flames are less steady
+        let air_mix = self.air_mix;
+
+        let t = micros as f32 / 1_000_000.0;
+        // more gas makes a taller flame, and a rich flame is taller again since it burns slower
+        let height_pct = (0.8 + 0.4 * intensity_mod) * (1.0 + 0.5 * (1.0 - air_mix));
+        let (center_x, _) = self.bounds.center();
+        let half_width = self.bounds.width() * 0.8;
+
+        for (i, led) in leds.iter_mut().enumerate() {
+            let shimmer = 1.0 + flicker_amount * perlin1(t * flicker_speed, i as f32);
+            let height = height_pct * self.bounds.height() * shimmer;
+            let up = ((led.coords.1 as f32 - self.bounds.min_y) / height).max(0.0); // 0 at the burner, 1 at the tip of the outer cone
+            let side = (led.coords.0 as f32 - center_x).abs() / half_width; // 0 in the middle, 1 at the sides
+
+            // the outer cone narrows from the full width at the burner to a point at the tip.
+            // a rich flame has a rounder, floppier tip
+            let cone_edge = (1.0 - up).max(0.0).powf(0.4 + 0.6 * air_mix);
+            let cone = ((cone_edge - side) * 2.5).clamp(0.0, 1.0);
+            // the inner cone is the same shape, shorter and narrower
+            let core_edge = (1.0 - up / core_fraction).max(0.0) * 0.6;
+            let core = ((core_edge - side) * 4.0).clamp(0.0, 1.0);
+
+            let blue = hsv_to_rgb(
+                cone_hue + (core_hue - cone_hue) * core,
+                1.0 - 0.5 * core,
+                0.5 * cone + 0.5 * core,
+            );
+            // unburnt soot glows yellow toward the top of a rich flame
+            let soot = (1.0 - air_mix) * ((up - 0.25) / 0.5).clamp(0.0, 1.0);
+            let yellow = hsv_to_rgb(soot_hue, 0.9, cone);
+            led.color = mix(blue, yellow, soot);
+        }
+    }
+}
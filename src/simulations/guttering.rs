@@ -0,0 +1,113 @@
+use rand::Rng;
+
+use crate::{
+    color::{heat_color, scale},
+    layout::Bounds,
+    noise::perlin1,
+    types::{frame_dt, Simulation, LED},
+};
+
+// a candle indoors is nearly still most of the time, and every so often a draft hits it: the flame
+// ducks down and leans over, then wobbles back upright over a second or two.
+// drafts arrive as a Poisson process (at random, with a set average rate), so they can't be predicted
+// and sometimes come in quick succession. a faster-burning flame catches more drafts.
+
+#[derive(Debug)]
+struct Gutter {
+    age: f32, // seconds since the draft hit
+    depth: f32, // how far the flame ducks, 0 to 1
+    lean: f32, // how far the flame is pushed sideways and which way, in layout units
+}
+
+impl Gutter {
+    const ATTACK_SECS: f32 = 0.15; // how long the draft takes to knock the flame down
+    const RECOVERY_SECS: f32 = 0.8; // how long (as a time constant) the flame takes to come back
+    const WOBBLE_SECS: f32 = 0.7; // how long each side-to-side wobble takes as it recovers
+
+    /// How much of the draft's strength is felt right now. Ramps up quickly, then decays.
+    fn envelope(&self) -> f32 {
+        if self.age < Self::ATTACK_SECS {
+            self.age / Self::ATTACK_SECS
+        } else {
+            f32::exp(-(self.age - Self::ATTACK_SECS) / Self::RECOVERY_SECS)
+        }
+    }
+
+    /// The sideways lean. It swings back past upright a couple of times while it settles.
+    fn sway(&self) -> f32 {
+        let recovering = (self.age - Self::ATTACK_SECS).max(0.0);
+        self.lean * self.envelope() * f32::cos(std::f32::consts::TAU * recovering / Self::WOBBLE_SECS)
+    }
+
+    fn is_over(&self) -> bool {
+        self.age > Self::ATTACK_SECS + 5.0 * Self::RECOVERY_SECS
+    }
+}
+
+#[derive(Debug)]
+pub struct GutteringCandleSim {
+    last_tick: u64,
+    gutters: Vec<Gutter>,
+    bounds: Bounds,
+}
+
+impl Simulation for GutteringCandleSim {
+    fn new(leds: &[LED]) -> Self where Self: Sized {
+        Self { last_tick: 0, gutters: vec![], bounds: Bounds::of(leds) }
+    }
+
+    fn get_name(&self) -> &str {
+        "Guttering candle"
+    }
+
+    fn tick(
+        &mut self,
+        leds: &mut Vec<LED>,
+        micros: u64,
+        intensity_mod: f32,
+    ) {
+        let drafts_per_second = 0.03 + 0.3 * intensity_mod; // config: how often, on average, a draft hits the flame
+        let calm_height = 1.1; // config: how tall the flame is between drafts, as a fraction of the layout height
+        let calm_flicker = 0.04; // config: how much the height wavers between drafts, as a fraction of the layout height
+        let max_lean = 0.4; // config: the furthest a draft can push the flame sideways, as a fraction of the layout width
+
+        let dt = frame_dt(self.last_tick, micros, 0.1);
+        self.last_tick = micros;
+        let t = micros as f32 / 1_000_000.0;
+        let mut rng = rand::thread_rng();
+
+        // in a Poisson process, the chance of at least one event in a short time dt is 1 - e^(-rate·dt)
+        if rng.gen::<f32>() < 1.0 - f32::exp(-drafts_per_second * dt) {
+            let lean = rng.gen_range(0.3..1.0) * max_lean * self.bounds.width();
+            self.gutters.push(Gutter {
+                age: 0.0,
+                depth: rng.gen_range(0.3..0.7),
+                lean: if rng.gen() { lean } else { -lean },
+            });
+        }
+        for gutter in &mut self.gutters {
+            gutter.age += dt;
+        }
+        self.gutters.retain(|gutter| !gutter.is_over());
+
+        // drafts that overlap add up, but the flame can't duck further than nearly out
+        let dip = self.gutters.iter().map(|gutter| gutter.depth * gutter.envelope()).sum::<f32>().min(0.9);
+        let lean: f32 = self.gutters.iter().map(Gutter::sway).sum();
+
+        let height = (calm_height + calm_flicker * perlin1(t * 3.0, 0.0)) * (1.0 - dip) * self.bounds.height();
+        let brightness = 1.0 - 0.4 * dip; // a ducked flame burns dimmer too
+        let (center_x, _) = self.bounds.center();
+        let half_width = self.bounds.width() * 0.6;
+
+        for led in leds.iter_mut() {
+            let x = led.coords.0 as f32;
+            let y = led.coords.1 as f32;
+            let height_pct = ((y - self.bounds.min_y) / height).max(0.0); // 0 at the bottom, 1 at the top of the flame
+            // the lean is strongest at the tip, the bottom stays on the wick
+            let axis_x = center_x + lean * height_pct.min(1.0).powi(2);
+            let side_pct = (x - axis_x).abs() / (half_width * (1.0 - 0.4 * height_pct.min(1.0)));
+            let heat = (1.0 - height_pct).min(1.0 - side_pct.powi(2)) * 1.4;
+            led.color = scale(heat_color(heat), brightness);
+        }
+    }
+}
@@ -0,0 +1,136 @@
+use rand::Rng;
+
+use crate::{
+    color::heat_color,
+    layout::{Bounds, NeighborGraph},
+    types::{frame_dt, Simulation, LED},
+};
+
+// our LEDs aren't on a grid, so instead of a grid-based fire this treats the LEDs as a graph:
+// each LED is connected to the LEDs near it, heat is injected at the bottom-most LEDs,
+// and every step heat spreads out along the edges (diffusion), gets carried upward along
+// edges that point up (convection), and slowly leaks away (decay).
+
+/// Which of the two neighbor graphs heat flows along.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Neighbors {
+    KNearest,
+    WithinDistance,
+}
+
+#[derive(Debug)]
+pub struct HeatGraphSim {
+    last_tick: u64,
+    neighbors: Neighbors, // which graph is in use
+    heat: Vec<f32>, // 0 to about 1 per LED
+    sources: Vec<usize>, // the bottom-most LEDs, where heat comes in
+    coords: Vec<(f32, f32)>, // a copy of the LED positions, since edges need to know which way is up
+    k_nearest_graph: NeighborGraph,
+    distance_graph: NeighborGraph,
+}
+
+impl HeatGraphSim {
+    const PRESETS: [&'static str; 2] = ["3 nearest neighbors", "Neighbors within 9 units"];
+
+    const fn graph(&self) -> &NeighborGraph {
+        match self.neighbors {
+            Neighbors::KNearest => &self.k_nearest_graph,
+            Neighbors::WithinDistance => &self.distance_graph,
+        }
+    }
+}
+
+impl Simulation for HeatGraphSim {
+    fn new(leds: &[LED]) -> Self where Self: Sized {
+        let source_height = 0.15; // config: LEDs in this fraction of the layout height (from the bottom) get heat injected
+        let bounds = Bounds::of(leds);
+        let sources = leds
+            .iter()
+            .enumerate()
+            .filter(|(_, led)| led.coords.1 as f32 <= bounds.min_y + source_height * bounds.height())
+            .map(|(i, _)| i)
+            .collect();
+
+        Self {
+            last_tick: 0,
+            neighbors: Neighbors::KNearest,
+            heat: vec![0.0; leds.len()],
+            sources,
+            coords: leds.iter().map(|led| (led.coords.0 as f32, led.coords.1 as f32)).collect(),
+            k_nearest_graph: NeighborGraph::k_nearest(leds, 3),
+            distance_graph: NeighborGraph::within_distance(leds, 9.0),
+        }
+    }
+
+    fn get_name(&self) -> &str {
+        "Heat diffusion graph"
+    }
+
+    fn get_presets(&self) -> &'static [&'static str] {
+        &Self::PRESETS
+    }
+
+    fn apply_preset(&mut self, preset: &str) -> bool {
+        self.neighbors = match preset {
+            "3 nearest neighbors" => Neighbors::KNearest,
+            "Neighbors within 9 units" => Neighbors::WithinDistance,
+            _ => return false,
+        };
+        true
+    }
+
+    fn tick(
+        &mut self,
+        leds: &mut Vec<LED>,
+        micros: u64,
+        intensity_mod: f32,
+    ) {
+        let injection = 2.0 + 3.0 * intensity_mod; // config: heat per second added at each source LED, on average
+        let diffusion = 1.5; // config: how fast heat evens out between neighbors, per second
+        let convection = 2.0 + 2.0 * intensity_mod; // config: how fast heat is carried upward, per second
+        let decay = 1.5; // config: how fast heat leaks away, per second
+
+        let dt = frame_dt(self.last_tick, micros, 0.05); // big steps would make diffusion unstable
+        self.last_tick = micros;
+        let mut rng = rand::thread_rng();
+
+        // inject heat at the bottom, unevenly so it flickers
+        for source in &self.sources {
+            self.heat[*source] += injection * rng.gen_range(0.0..2.0) * dt;
+        }
+
+        // work out all the flows from the current heat before applying any of them
+        let mut change = vec![0.0; self.heat.len()];
+        for i in 0..self.heat.len() {
+            let neighbors = self.graph().neighbors(i);
+            // share of this LED's heat that convection carries up, split between the neighbors above it
+            let upward: f32 = neighbors
+                .iter()
+                .map(|(j, distance)| ((self.coords[*j].1 - self.coords[i].1) / distance).max(0.0))
+                .sum();
+            for (j, distance) in neighbors {
+                // diffusion is symmetric, so each edge is only counted from one end
+                if i < *j {
+                    let flow = diffusion * (self.heat[*j] - self.heat[i]) * dt;
+                    change[i] += flow;
+                    change[*j] -= flow;
+                }
+                let rise = ((self.coords[*j].1 - self.coords[i].1) / distance).max(0.0);
+                if upward > 0.0 && rise > 0.0 {
+                    let carried = convection * self.heat[i] * dt * rise / upward;
+                    change[i] -= carried;
+                    change[*j] += carried;
+                }
+            }
+            if upward == 0.0 {
+                // nothing above this LED, so the rising heat escapes out the top of the flame
+                change[i] -= convection * self.heat[i] * dt;
+            }
+        }
+
+        for (i, led) in leds.iter_mut().enumerate() {
+            self.heat[i] = ((self.heat[i] + change[i]) * f32::exp(-decay * dt)).clamp(0.0, 1.5);
+            led.color = heat_color(self.heat[i]);
+        }
+    }
+}
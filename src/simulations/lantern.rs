@@ -0,0 +1,151 @@
+use std::collections::VecDeque;
+
+use crate::{
+    color::{heat_color, scale},
+    layout::{with_role, Bounds},
+    noise::perlin1,
+    types::{frame_dt, Role, Simulation, LED, RGB},
+};
+
+// an oil lantern: a small, steady flame in the middle, behind glass. the outer LEDs are the glass,
+// lit by light bouncing off it, so they follow the flame's brightness but late and smoothed out,
+// each by a different amount, and they shift slowly as if the glass were uneven.
+// the LEDs at the very top are the chimney, warmed by the hot air rising off the flame.
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Part {
+    Flame,
+    /// light reflected off the glass, `delay` seconds late and smoothed over `smoothing` seconds
+    Glass { delay: f32, smoothing: f32 },
+    Chimney,
+}
+
+#[derive(Debug)]
+pub struct LanternSim {
+    last_tick: u64,
+    parts: Vec<Part>,
+    smoothed: Vec<f32>, // each LED's brightness after smoothing
+    history: VecDeque<(u64, f32)>, // recent flame brightness with when it happened, for the reflections to lag behind
+    flame_center: (f32, f32),
+    flame_radius: f32,
+}
+
+impl LanternSim {
+    const MAX_DELAY_SECS: f32 = 0.6;
+
+    /// The flame's brightness `delay` seconds ago, or the oldest we remember.
+    fn flame_brightness_ago(&self, micros: u64, delay: f32) -> f32 {
+        let when = micros.saturating_sub((delay * 1_000_000.0) as u64);
+        self.history
+            .iter()
+            .rev()
+            .find(|(time, _)| *time <= when)
+            .or_else(|| self.history.front())
+            .map_or(0.0, |(_, brightness)| *brightness)
+    }
+}
+
+impl Simulation for LanternSim {
+    fn new(leds: &[LED]) -> Self where Self: Sized {
+        let flame_height = 0.35; // config: how high up the flame sits, as a fraction of the layout height
+        let flame_radius = 0.4; // config: how far out from the flame LEDs count as flame, as a fraction of the layout width
+        let chimney_height = 0.85; // config: LEDs above this fraction of the layout height are the chimney
+
+        let bounds = Bounds::of(leds);
+        let (center_x, _) = bounds.center();
+        let flame_center = (center_x, bounds.min_y + flame_height * bounds.height());
+        let flame_radius = flame_radius * bounds.width();
+        // the layout's flame LEDs are the flame, and everything around them, base and wick included, is the glass.
+        // if the layout doesn't tag any, the flame is whatever is close enough to where it sits
+        let flame_leds = with_role(leds, Role::Flame);
+        let parts = leds
+            .iter()
+            .enumerate()
+            .map(|(i, led)| {
+                let x = led.coords.0 as f32;
+                let y = led.coords.1 as f32;
+                let is_flame = if flame_leds.is_empty() {
+                    (x - flame_center.0).hypot(y - flame_center.1) <= flame_radius
+                } else {
+                    flame_leds.contains(&i)
+                };
+                if is_flame {
+                    Part::Flame
+                } else if y > bounds.min_y + chimney_height * bounds.height() {
+                    Part::Chimney
+                } else {
+                    // spread the delays and smoothing out by position, so neighboring panes don't match
+                    let spread = (i as f32 * 0.618).fract();
+                    Part::Glass { delay: 0.1 + 0.5 * spread, smoothing: 0.2 + 0.4 * (1.0 - spread) }
+                }
+            })
+            .collect();
+        Self {
+            last_tick: 0,
+            parts,
+            smoothed: vec![0.0; leds.len()],
+            history: VecDeque::new(),
+            flame_center,
+            flame_radius,
+        }
+    }
+
+    fn get_name(&self) -> &str {
+        "Oil lantern"
+    }
+
+    fn tick(
+        &mut self,
+        leds: &mut Vec<LED>,
+        micros: u64,
+        intensity_mod: f32,
+    ) {
+        let flicker = 0.05 + 0.1 * intensity_mod; // config: how much the flame's brightness wavers. a lantern is steady
+        let reflection_strength = 0.4; // config: how bright the glass gets compared to the flame
+        let glass_color = RGB { r: 255, g: 140, b: 40 }; // config: the color of the light off the glass
+        let chimney_color = RGB { r: 150, g: 40, b: 0 }; // config: the color of the warm chimney
+        let chimney_smoothing = 2.0; // config: how slowly the chimney warms and cools, in seconds
+
+        let dt = frame_dt(self.last_tick, micros, 0.1);
+        self.last_tick = micros;
+        let t = micros as f32 / 1_000_000.0;
+
+        let flame_brightness = (0.85 + flicker * (perlin1(t * 2.0, 0.0) + 0.5 * perlin1(t * 7.0, 1.0))).clamp(0.0, 1.0);
+        self.history.push_back((micros, flame_brightness));
+        let oldest = micros.saturating_sub((Self::MAX_DELAY_SECS * 1_000_000.0) as u64);
+        while self.history.len() > 1 && self.history[1].0 <= oldest {
+            self.history.pop_front();
+        }
+
+        for (i, led) in leds.iter_mut().enumerate() {
+            let (target, smoothing) = match self.parts[i] {
+                Part::Flame => (flame_brightness, 0.0),
+                Part::Glass { delay, smoothing } => (self.flame_brightness_ago(micros, delay), smoothing),
+                Part::Chimney => (flame_brightness, chimney_smoothing),
+            };
+            // exponential smoothing: close a fixed fraction of the gap every second
+            self.smoothed[i] = if smoothing == 0.0 {
+                target
+            } else {
+                self.smoothed[i] + (target - self.smoothed[i]) * (1.0 - f32::exp(-dt / smoothing))
+            };
+            let brightness = self.smoothed[i];
+
+            led.color = match self.parts[i] {
+                Part::Flame => {
+                    // hottest in the middle of the flame
+                    let x = led.coords.0 as f32;
+                    let y = led.coords.1 as f32;
+                    let from_center = (x - self.flame_center.0).hypot(y - self.flame_center.1) / self.flame_radius;
+                    heat_color(brightness * (1.0 - 0.5 * from_center))
+                }
+                Part::Glass { .. } => {
+                    // the glass isn't perfectly even, so each pane's reflection drifts a little on its own
+                    let drift = 1.0 + 0.3 * perlin1(t * 0.2, i as f32);
+                    scale(glass_color, brightness * reflection_strength * drift)
+                }
+                Part::Chimney => scale(chimney_color, brightness * 0.25),
+            };
+        }
+    }
+}
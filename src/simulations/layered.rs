@@ -0,0 +1,158 @@
+use std::time::Duration;
+
+use crate::{layout::blank_leds, types::{Simulation, LED, RGB}};
+
+use super::{
+    candle_colored::CandleColoredSim, decorators::BrightnessLfo, height_flicker::HeightFlickerSim, sparks::SparksSim,
+};
+
+/// How a layer is combined with the layers below it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BlendMode {
+    /// brighten by adding the layer's light, like stacking light sources
+    Add,
+    /// brighten, but never blow out as fast as `Add`
+    Screen,
+    /// darken, for masks and shading
+    Multiply,
+    /// keep whichever channel is brighter
+    Max,
+    /// paint the layer over what's below, with the layer's opacity as alpha
+    Alpha,
+}
+
+impl BlendMode {
+    fn blend(self, below: f32, above: f32) -> f32 {
+        match self {
+            Self::Add => (below + above).min(1.0),
+            Self::Screen => 1.0 - (1.0 - below) * (1.0 - above),
+            Self::Multiply => below * above,
+            Self::Max => below.max(above),
+            Self::Alpha => above,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Layer {
+    simulation: Box<dyn Simulation>,
+    blend: BlendMode,
+    opacity: f32, // 0 to 1, how much of the blended result to use
+    leds: Vec<LED>, // the buffer this layer draws into
+}
+
+impl Layer {
+    #[must_use] pub fn new(simulation: Box<dyn Simulation>, blend: BlendMode, opacity: f32) -> Self {
+        Self { simulation, blend, opacity, leds: vec![] }
+    }
+}
+
+#[derive(Debug)]
+pub struct LayeredSim { // stacks other simulations on top of each other
+    layers: Vec<Layer>, // bottom layer first
+}
+
+impl LayeredSim {
+    // presets switch how the flicker glowing through the candle is blended. the sparks are mostly black,
+    // so anything but adding them (or keeping the brighter) would blank out the flame underneath
+    const PRESETS: [&'static str; 5] = ["Screen", "Add", "Max", "Multiply", "Alpha"];
+
+    /// Build a stack out of `layers`, bottom layer first.
+    #[must_use] pub fn from_layers(leds: &[LED], mut layers: Vec<Layer>) -> Self {
+        for layer in &mut layers {
+            layer.leds = blank_leds(leds);
+        }
+        Self { layers }
+    }
+}
+
+impl Simulation for LayeredSim {
+    fn new(leds: &[LED]) -> Self where Self: Sized {
+        // the default stack: the colored candle slowly breathing, with the height flicker glowing through it
+        // and sparks rising off the top
+        let breathing_candle = BrightnessLfo::wrap(CandleColoredSim::new(leds), leds, Duration::from_secs(6), 0.3);
+        Self::from_layers(leds, vec![
+            Layer::new(Box::new(breathing_candle), BlendMode::Alpha, 1.0),
+            Layer::new(Box::new(HeightFlickerSim::new(leds)), BlendMode::Screen, 0.35),
+            Layer::new(Box::new(SparksSim::new(leds)), BlendMode::Add, 1.0),
+        ])
+    }
+
+    fn get_name(&self) -> &'static str {
+        "Layered flame"
+    }
+
+    fn get_presets(&self) -> &'static [&'static str] {
+        &Self::PRESETS
+    }
+
+    fn apply_preset(&mut self, preset: &str) -> bool {
+        // one blend mode for each layer above the bottom one
+        let blends: &[BlendMode] = match preset {
+            "Screen" => &[BlendMode::Screen, BlendMode::Add],
+            "Add" => &[BlendMode::Add, BlendMode::Add],
+            "Max" => &[BlendMode::Max, BlendMode::Max],
+            "Multiply" => &[BlendMode::Multiply, BlendMode::Add],
+            "Alpha" => &[BlendMode::Alpha, BlendMode::Add],
+            _ => return false,
+        };
+        for (layer, blend) in self.layers.iter_mut().skip(1).zip(blends) {
+            layer.blend = *blend;
+        }
+        true
+    }
+
+    fn start(&mut self, micros: u64) {
+        for layer in &mut self.layers {
+            layer.simulation.start(micros);
+        }
+    }
+
+    fn stop(&mut self, micros: u64) {
+        for layer in &mut self.layers {
+            layer.simulation.stop(micros);
+        }
+    }
+
+    fn is_stopped(&self) -> bool {
+        self.layers.iter().all(|layer| layer.simulation.is_stopped())
+    }
+
+    fn get_status(&self) -> Option<String> {
+        let statuses: Vec<String> = self.layers.iter().filter_map(|layer| layer.simulation.get_status()).collect();
+        (!statuses.is_empty()).then(|| statuses.join("; "))
+    }
+
+    fn tick(
+        &mut self,
+        leds: &mut Vec<LED>,
+        micros: u64,
+        intensity_mod: f32,
+    ) {
+        // every layer draws into its own buffer so simulations that build on their last frame still work
+        for layer in &mut self.layers {
+            layer.simulation.tick(&mut layer.leds, micros, intensity_mod);
+        }
+
+        for (i, led) in leds.iter_mut().enumerate() {
+            let mut color = RGB { r: 0.0, g: 0.0, b: 0.0 };
+            for layer in &self.layers {
+                let above = layer.leds[i].color;
+                let mix = |below: f32, above: u8| {
+                    let above = f32::from(above) / 255.0;
+                    below + (layer.blend.blend(below, above) - below) * layer.opacity
+                };
+                color = RGB {
+                    r: mix(color.r, above.r),
+                    g: mix(color.g, above.g),
+                    b: mix(color.b, above.b),
+                };
+            }
+            led.color = RGB {
+                r: (color.r * 255.0).round() as u8,
+                g: (color.g * 255.0).round() as u8,
+                b: (color.b * 255.0).round() as u8,
+            };
+        }
+    }
+}
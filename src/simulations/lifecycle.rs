@@ -0,0 +1,171 @@
+//! A wrapper that lights any flame the way a real one lights: it grows up from the lowest LEDs when it starts,
+//! and when it's stopped it shrinks back down and leaves a few embers glowing before going dark.
+//! With [`Lifecycle::with_smoke`], a wisp of smoke rises off it once the flame is gone, like a blown-out candle.
+use std::time::Duration;
+
+use crate::{
+    color::scale,
+    layout::{blank_leds, Bounds},
+    types::{Simulation, LED, RGB},
+};
+
+use super::smoke::SmokeWisp;
+
+#[derive(Debug)]
+pub struct Lifecycle<S: Simulation> {
+    inner: S,
+    buffer: Vec<LED>,
+    ignite_micros: u64, // how long the flame takes to grow to full size
+    burnout_micros: u64, // how long the flame takes to die down once stopped
+    started_at: Option<u64>,
+    stopped_at: Option<u64>,
+    burnt_out: bool, // set once the burnout has finished
+    smoke: Option<SmokeWisp>, // the smoke to finish with, if any
+    bounds: Bounds,
+    name: String,
+}
+
+impl<S: Simulation> Lifecycle<S> {
+    #[must_use] pub fn wrap(inner: S, leds: &[LED]) -> Self {
+        let name = format!("{}, with ignition", inner.get_name());
+        Self {
+            inner,
+            buffer: blank_leds(leds),
+            ignite_micros: 3_000_000,
+            burnout_micros: 4_000_000,
+            started_at: None,
+            stopped_at: None,
+            burnt_out: false,
+            smoke: None,
+            bounds: Bounds::of(leds),
+            name,
+        }
+    }
+
+    #[must_use] pub fn with_durations(mut self, ignite: Duration, burnout: Duration) -> Self {
+        self.ignite_micros = ignite.as_micros().try_into().unwrap_or(u64::MAX);
+        self.burnout_micros = burnout.as_micros().try_into().unwrap_or(u64::MAX);
+        self
+    }
+
+    /// Finish the burnout with a wisp of smoke rising from where the flame was. The flame only counts as stopped once
+    /// the smoke has faded too.
+    #[must_use] pub fn with_smoke(mut self) -> Self {
+        self.smoke = Some(SmokeWisp::new(&self.buffer));
+        self
+    }
+
+    /// How much of an LED at height `y` is lit when the top of the flame is at `level`.
+    /// The edge is soft so LEDs fade in and out instead of popping.
+    fn coverage(level: f32, y: f32) -> f32 {
+        let edge_width = 4.0; // config: how soft the top of the growing/shrinking flame is
+        ((level - y) / edge_width + 0.5).clamp(0.0, 1.0)
+    }
+}
+
+impl<S: Simulation> Simulation for Lifecycle<S> {
+    fn new(leds: &[LED]) -> Self where Self: Sized {
+        Self::wrap(S::new(leds), leds)
+    }
+
+    fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    fn lit_by_power_on(&self) -> bool {
+        false // it plays its own ignition
+    }
+
+    delegate_simulation!(inner => get_presets, apply_preset, get_status);
+
+    fn start(&mut self, micros: u64) {
+        self.started_at = Some(micros);
+        self.stopped_at = None;
+        self.burnt_out = false;
+        if let Some(smoke) = &mut self.smoke {
+            smoke.clear();
+        }
+        self.inner.start(micros);
+    }
+
+    fn stop(&mut self, micros: u64) {
+        self.stopped_at = Some(micros);
+        if let Some(smoke) = &mut self.smoke {
+            smoke.clear();
+        }
+        self.inner.stop(micros);
+    }
+
+    fn is_stopped(&self) -> bool {
+        self.burnt_out && self.inner.is_stopped()
+    }
+
+    fn tick(
+        &mut self,
+        leds: &mut Vec<LED>,
+        micros: u64,
+        intensity_mod: f32,
+    ) {
+        let ember_color = RGB { r: 140, g: 25, b: 0 }; // config: color of the embers left behind as the flame dies
+        let ember_height = 0.3; // config: what fraction of the layout (from the bottom) glows with embers while burning out
+
+        let started_at = *self.started_at.get_or_insert(micros); // if nobody called start, start now
+        self.inner.tick(&mut self.buffer, micros, intensity_mod);
+
+        let height = self.bounds.height();
+        let ignite_progress = if self.ignite_micros == 0 {
+            1.0
+        } else {
+            (micros.saturating_sub(started_at) as f32 / self.ignite_micros as f32).min(1.0)
+        };
+        let burnout_progress = self.stopped_at.map(|stopped_at| {
+            if self.burnout_micros == 0 {
+                1.0
+            } else {
+                (micros.saturating_sub(stopped_at) as f32 / self.burnout_micros as f32).min(1.0)
+            }
+        });
+
+        for (led, source) in leds.iter_mut().zip(&self.buffer) {
+            let y = led.coords.1 as f32;
+            // igniting: the top of the flame climbs from just below the lowest LED to past the highest one,
+            // getting brighter as it grows
+            let ignite_level = self.bounds.min_y - 2.0 + ignite_progress * (height + 4.0);
+            let mut brightness = Self::coverage(ignite_level, y) * ignite_progress.sqrt();
+            let mut ember = 0.0;
+
+            if let Some(burnout_progress) = burnout_progress {
+                // burning out: the top of the flame sinks back down, quicker than it grew
+                let burnout_level = self.bounds.max_y + 2.0 - (burnout_progress * 1.5).min(1.0) * (height + 4.0);
+                let coverage = Self::coverage(burnout_level, y);
+                brightness *= coverage;
+                // LEDs the flame has left behind near the bottom glow like embers, then fade out
+                let in_ember_bed = y <= self.bounds.min_y + ember_height * height;
+                if in_ember_bed {
+                    ember = (1.0 - coverage) * (1.0 - burnout_progress).powi(2);
+                }
+            }
+
+            let flame = scale(source.color, brightness);
+            let ember = scale(ember_color, ember);
+            led.color = RGB {
+                r: flame.r.max(ember.r),
+                g: flame.g.max(ember.g),
+                b: flame.b.max(ember.b),
+            };
+        }
+
+        if let (Some(smoke), Some(burnout_progress)) = (&mut self.smoke, burnout_progress) {
+            // the smoke starts rising as soon as the flame has sunk out of sight
+            if burnout_progress * 1.5 >= 1.0 && !smoke.is_puffed() {
+                smoke.puff(micros);
+            }
+            smoke.draw(leds, micros);
+        }
+
+        let smoke_done = self.smoke.as_ref().is_none_or(|smoke| smoke.is_puffed() && smoke.is_done(micros));
+        if burnout_progress == Some(1.0) && smoke_done {
+            self.burnt_out = true;
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use crate::{
+    color::heat_color,
+    layout::Bounds,
+    markov::MarkovModel,
+    types::{Simulation, LED},
+};
+
+// new flicker made up by a Markov chain (see `markov.rs`) instead of played back from a recording,
+// so it never repeats and fits on the board. the chain picks a brightness level every step,
+// and the flame eases from one level to the next so the steps don't show.
+// brighter levels make a taller, hotter flame, the same way the recorded flicker does.
+
+#[derive(Debug)]
+pub struct MarkovFlickerSim {
+    model: MarkovModel,
+    state: usize, // the level the flame is easing away from
+    next_state: usize, // the level the flame is easing toward
+    step_started: u64, // micros when the flame left `state`
+    bounds: Bounds,
+    name: String,
+}
+
+impl MarkovFlickerSim {
+    /// Flicker using `model`. `source` says where the model came from, for the name in the menu.
+    #[must_use] pub fn from_model(leds: &[LED], model: MarkovModel, source: &str) -> Self {
+        let mut sim = Self::new(leds);
+        sim.model = model;
+        sim.name = format!("Markov flicker ({source})");
+        sim
+    }
+}
+
+impl Simulation for MarkovFlickerSim {
+    fn new(leds: &[LED]) -> Self where Self: Sized {
+        let top = crate::markov::STATES - 1;
+        Self {
+            model: MarkovModel::default(),
+            state: top,
+            next_state: top,
+            step_started: 0,
+            bounds: Bounds::of(leds),
+            name: "Markov flicker".to_string(),
+        }
+    }
+
+    fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    fn tick(
+        &mut self,
+        leds: &mut Vec<LED>,
+        micros: u64,
+        intensity_mod: f32,
+    ) {
+        let min_height = 0.3; // config: how tall the flame is at the dimmest level, as a fraction of the layout height
+        let max_height = 0.7 + 0.5 * intensity_mod; // config: how tall the flame is at the brightest level
+
+        let step_micros = u64::from(self.model.step_micros);
+        let steps = micros.saturating_sub(self.step_started) / step_micros;
+        self.step_started += steps * step_micros;
+        let mut rng = rand::thread_rng();
+        // after a long pause, a few steps are plenty to lose track of where the flame was
+        for _ in 0..steps.min(100) {
+            self.state = self.next_state;
+            self.next_state = self.model.next_state(self.state, &mut rng);
+        }
+
+        let progress = micros.saturating_sub(self.step_started) as f32 / step_micros as f32;
+        let from = MarkovModel::brightness_of(self.state);
+        let to = MarkovModel::brightness_of(self.next_state);
+        let value = from + (to - from) * progress;
+
+        let height = (min_height + (max_height - min_height) * value) * self.bounds.height();
+        for led in leds.iter_mut() {
+            let height_pct = (led.coords.1 as f32 - self.bounds.min_y) / height.max(0.01);
+            led.color = heat_color((1.0 - height_pct) * (0.7 + 0.6 * value));
+        }
+    }
+}
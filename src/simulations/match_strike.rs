@@ -0,0 +1,135 @@
+//! A "power on" animation: a match is struck at the bottom of the flame, flashes, sputters as the sulfur
+//! burns off, settles into a small flame, and then hands off to a follow-up simulation.
+//! [`Strike`] is the animation on its own, drawn over whatever the follow-up has drawn, which the app uses to
+//! light the first simulation it shows. [`MatchStrike`] wraps a follow-up so it can be picked from the menu.
+use rand::Rng;
+
+use crate::{
+    color::{heat_color, hsv_to_rgb, mix, scale},
+    layout::{blank_leds, distance, Bounds},
+    noise::perlin1,
+    types::{Simulation, LED, RGB},
+};
+
+use super::candle_colored::CandleColoredSim;
+
+const FLASH_SECS: f32 = 0.2; // the bright flash of the head catching
+const SPUTTER_SECS: f32 = 1.2; // the sulfur burning off, pale and unsteady
+const SETTLE_SECS: f32 = 1.2; // the wood catching, a small flame that steadies and grows
+const HANDOFF_SECS: f32 = 1.0; // fading from the match's flame into the follow-up simulation
+
+#[derive(Clone, Debug)]
+pub struct Strike {
+    strike_led: Option<usize>, // the LED the match is struck at. None if there are no LEDs to strike at
+    started_at: u64,
+}
+
+impl Strike {
+    /// Strike a match at `micros`.
+    #[must_use] pub fn new(leds: &[LED], micros: u64) -> Self {
+        // strike at the bottom of the flame, as close to the middle as possible
+        let bounds = Bounds::of(leds);
+        let (center_x, _) = bounds.center();
+        let strike_led = (0..leds.len()).min_by(|a, b| {
+            let score = |led: &LED| (led.coords.1 as f32 - bounds.min_y) * 2.0 + (led.coords.0 as f32 - center_x).abs();
+            score(&leds[*a]).total_cmp(&score(&leds[*b]))
+        });
+        Self { strike_led, started_at: micros }
+    }
+
+    /// Whether the match has finished handing off to the follow-up.
+    #[must_use] pub fn is_done(&self, micros: u64) -> bool {
+        micros.saturating_sub(self.started_at) as f32 / 1_000_000.0 >= FLASH_SECS + SPUTTER_SECS + SETTLE_SECS + HANDOFF_SECS
+    }
+
+    /// Draw the match into `leds`, fading into the follow-up's frame `follow_up` as it hands off.
+    pub fn draw(&self, leds: &mut [LED], follow_up: &[LED], micros: u64) {
+        let Some(strike_led) = self.strike_led else { return };
+        let secs = micros.saturating_sub(self.started_at) as f32 / 1_000_000.0;
+        let handoff_start = FLASH_SECS + SPUTTER_SECS + SETTLE_SECS;
+        let handoff = ((secs - handoff_start) / HANDOFF_SECS).clamp(0.0, 1.0);
+        let strike = leds[strike_led].clone();
+        let mut rng = rand::thread_rng();
+        for (led, follow_up) in leds.iter_mut().zip(follow_up) {
+            let color = Self::match_color(secs, distance(led, &strike), &mut rng);
+            led.color = mix(color, follow_up.color, handoff);
+        }
+    }
+
+    /// The match's own light on an LED `distance` away from where it was struck, `secs` after the strike.
+    fn match_color(secs: f32, distance: f32, rng: &mut impl Rng) -> RGB<u8> {
+        if secs < FLASH_SECS {
+            // a white-yellow flash that lights up the LEDs nearby too
+            let fade = 1.0 - secs / FLASH_SECS;
+            let reach = (1.0 - distance / 12.0).max(0.0);
+            return scale(RGB { r: 255, g: 240, b: 190 }, fade.sqrt() * reach);
+        }
+        let secs = secs - FLASH_SECS;
+        if secs < SPUTTER_SECS {
+            // pale and sputtering: brightness jumps around every frame while the sulfur burns off
+            let radius = 5.0 + 5.0 * secs / SPUTTER_SECS;
+            let reach = (1.0 - distance / radius).max(0.0);
+            let sputter = rng.gen_range(0.3..1.0);
+            return hsv_to_rgb(50.0 + 20.0 * rng.gen::<f32>(), 0.4, sputter * reach);
+        }
+        // a small flame that calms down and grows. the handoff keeps drawing it while it fades out
+        let secs = secs - SPUTTER_SECS;
+        let settled = (secs / SETTLE_SECS).min(1.0);
+        let radius = 10.0 + 6.0 * settled;
+        let flicker = perlin1(secs * 4.0, 0.0) * 0.3 * (1.0 - settled);
+        heat_color((1.0 - distance / radius) * 1.2 + flicker)
+    }
+}
+
+/// The match strike as a simulation of its own, handing off to `follow_up`.
+#[derive(Debug)]
+pub struct MatchStrike {
+    follow_up: Box<dyn Simulation>,
+    buffer: Vec<LED>,
+    strike: Option<Strike>,
+    name: String,
+}
+
+impl MatchStrike {
+    #[must_use] pub fn wrap(follow_up: Box<dyn Simulation>, leds: &[LED]) -> Self {
+        let name = format!("Match strike, then {}", follow_up.get_name());
+        Self { follow_up, buffer: blank_leds(leds), strike: None, name }
+    }
+}
+
+impl Simulation for MatchStrike {
+    fn new(leds: &[LED]) -> Self where Self: Sized {
+        Self::wrap(Box::new(CandleColoredSim::new(leds)), leds)
+    }
+
+    fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    fn lit_by_power_on(&self) -> bool {
+        false // it strikes its own match
+    }
+
+    delegate_simulation!(follow_up => get_presets, apply_preset, stop, is_stopped, get_status);
+
+    fn start(&mut self, micros: u64) {
+        self.strike = Some(Strike::new(&self.buffer, micros));
+        self.follow_up.start(micros);
+    }
+
+    fn tick(
+        &mut self,
+        leds: &mut Vec<LED>,
+        micros: u64,
+        intensity_mod: f32,
+    ) {
+        // if nobody called start, start now
+        let strike = self.strike.get_or_insert_with(|| Strike::new(&self.buffer, micros));
+        // the follow-up runs the whole time so it's already going when it takes over
+        self.follow_up.tick(&mut self.buffer, micros, intensity_mod);
+        leds.clone_from(&self.buffer);
+        if !strike.is_done(micros) {
+            strike.draw(leds, &self.buffer, micros);
+        }
+    }
+}
@@ -0,0 +1,65 @@
+use crate::{
+    color::heat_color,
+    layout::Bounds,
+    noise::fbm3,
+    types::{Simulation, LED},
+};
+
+// instead of every LED following the same global flicker, this samples a 3D noise field at each LED
+// (x and y from the layout, z is time). the field scrolls upward so features rise like hot gas,
+// and it's shaped into a flame by fading the heat out with height and distance from the middle.
+
+#[derive(Debug)]
+pub struct NoiseFlameSim {
+    bounds: Bounds,
+}
+
+impl Simulation for NoiseFlameSim {
+    fn new(leds: &[LED]) -> Self where Self: Sized {
+        Self { bounds: Bounds::of(leds) }
+    }
+
+    fn get_name(&self) -> &str {
+        "Noise flame"
+    }
+
+    fn tick(
+        &mut self,
+        leds: &mut Vec<LED>,
+        micros: u64,
+        intensity_mod: f32,
+    ) {
+        let spatial_scale = 0.12; // config: how big the noise features are relative to the layout. smaller = bigger blobs
+        let rise_speed = 2.5; // config: how fast the noise scrolls upward, in noise units per second
+        let churn_speed = 0.6; // config: how fast the noise changes shape, in noise units per second
+        let flame_height = 0.55 + 0.55 * intensity_mod; // how tall the flame is, as a fraction of the layout height
+        let turbulence = 0.35 + 0.5 * intensity_mod; // how much the noise can push heat around
+
+        let t = micros as f32 / 1_000_000.0;
+        let (center_x, _) = self.bounds.center();
+        let half_width = self.bounds.width() / 2.0;
+
+        for led in leds.iter_mut() {
+            let x = led.coords.0 as f32;
+            let y = led.coords.1 as f32;
+            let height_pct = (y - self.bounds.min_y) / self.bounds.height(); // 0 at the bottom, 1 at the top
+            let side_pct = (x - center_x).abs() / half_width; // 0 in the middle, 1 at the edges
+
+            // scroll the field down as time goes on, so the features we sample rise up through the LEDs
+            let noise = fbm3(
+                (x - center_x) * spatial_scale,
+                (y - self.bounds.min_y) * spatial_scale - t * rise_speed,
+                t * churn_speed,
+                3,
+            ); // roughly -1 to 1
+
+            // the flame's shape: hottest at the bottom middle, narrowing and cooling toward the tip
+            let vertical_falloff = 1.0 - height_pct / flame_height;
+            let flame_width = 1.0 - 0.6 * height_pct; // the flame gets narrower as it rises
+            let horizontal_falloff = 1.0 - (side_pct / flame_width).powi(2);
+            let heat = vertical_falloff.min(horizontal_falloff.max(0.0) + 0.3) + noise * turbulence;
+
+            led.color = heat_color(heat);
+        }
+    }
+}
@@ -46,6 +46,10 @@ impl Simulation for RainbowFloodSim {
         "Simulation framework test (not a flame)"
     }
 
+    fn lit_by_power_on(&self) -> bool {
+        false // a test pattern, not a flame
+    }
+
     fn tick(
             &mut self,
             leds: &mut Vec<LED>, // the LEDs we're controlling. We can change their colors here
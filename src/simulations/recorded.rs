@@ -0,0 +1,74 @@
+use crate::{
+    color::heat_color,
+    layout::Bounds,
+    recording::Recording,
+    types::{Simulation, LED},
+};
+
+// plays back brightness measured from a real candle (e.g. with a photodiode) instead of making flicker up.
+// see `recording.rs` for the file format. a brighter reading makes a taller, hotter flame.
+// with more than one channel, the channels are spread across the layout from left to right,
+// so a sensor on each side of the candle shows up on that side.
+
+#[derive(Debug)]
+pub struct RecordedFlickerSim {
+    recording: Recording,
+    bounds: Bounds,
+    name: String,
+}
+
+impl RecordedFlickerSim {
+    /// Play back `recording`. `source` says where it came from, for the name in the menu.
+    #[must_use] pub fn from_recording(leds: &[LED], recording: Recording, source: &str) -> Self {
+        Self { recording, bounds: Bounds::of(leds), name: format!("Recorded flicker ({source})") }
+    }
+}
+
+impl Simulation for RecordedFlickerSim {
+    fn new(leds: &[LED]) -> Self where Self: Sized {
+        // without a recording there's nothing to play, so hold a steady half-bright flame
+        Self { recording: Recording::steady(0.5), bounds: Bounds::of(leds), name: "Recorded flicker".to_string() }
+    }
+
+    fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    fn get_status(&self) -> Option<String> {
+        Some(format!(
+            "{} samples, {} channel(s), looping every {:.1}s",
+            self.recording.len(),
+            self.recording.channels(),
+            self.recording.loop_secs(),
+        ))
+    }
+
+    fn tick(
+        &mut self,
+        leds: &mut Vec<LED>,
+        micros: u64,
+        intensity_mod: f32,
+    ) {
+        let min_height = 0.3; // config: how tall the flame is at the dimmest reading, as a fraction of the layout height
+        let max_height = 0.7 + 0.5 * intensity_mod; // config: how tall the flame is at the brightest reading
+
+        let values = self.recording.sample_at(micros as f32 / 1_000_000.0);
+        for led in leds.iter_mut() {
+            let x = led.coords.0 as f32;
+            let y = led.coords.1 as f32;
+            // with several channels, blend between the two whose spot on the layout this LED is between
+            let across = if values.len() > 1 && self.bounds.width() > 0.0 {
+                (x - self.bounds.min_x) / self.bounds.width() * (values.len() - 1) as f32
+            } else {
+                0.0
+            };
+            let left = (across.floor() as usize).min(values.len() - 1);
+            let right = (left + 1).min(values.len() - 1);
+            let value = values[left] + (values[right] - values[left]) * (across - left as f32);
+
+            let height = (min_height + (max_height - min_height) * value) * self.bounds.height();
+            let height_pct = (y - self.bounds.min_y) / height.max(0.01);
+            led.color = heat_color((1.0 - height_pct) * (0.7 + 0.6 * value));
+        }
+    }
+}
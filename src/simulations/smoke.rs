@@ -0,0 +1,119 @@
+//! The thin trail of smoke that rises off a candle after it's blown out. [`SmokeWisp`] is the effect on its own,
+//! for any flame to finish with; [`SmokeWispSim`] puffs one every few seconds so it can be watched from the menu.
+use crate::{
+    color::scale,
+    layout::Bounds,
+    noise::perlin1,
+    types::{Simulation, LED, RGB},
+};
+
+#[derive(Clone, Debug)]
+pub struct SmokeWisp {
+    puffed_at: Option<u64>,
+    bounds: Bounds,
+}
+
+impl SmokeWisp {
+    const DURATION_SECS: f32 = 4.0; // how long until the smoke has faded away completely
+
+    #[must_use] pub fn new(leds: &[LED]) -> Self {
+        Self { puffed_at: None, bounds: Bounds::of(leds) }
+    }
+
+    /// Start a new wisp rising from where the tip of the flame was.
+    pub fn puff(&mut self, micros: u64) {
+        self.puffed_at = Some(micros);
+    }
+
+    /// Forget any wisp, so the next one can be puffed.
+    pub fn clear(&mut self) {
+        self.puffed_at = None;
+    }
+
+    #[must_use] pub fn is_puffed(&self) -> bool {
+        self.puffed_at.is_some()
+    }
+
+    #[must_use] pub fn is_done(&self, micros: u64) -> bool {
+        self.puffed_at.is_none_or(|puffed_at| micros.saturating_sub(puffed_at) as f32 / 1_000_000.0 >= Self::DURATION_SECS)
+    }
+
+    /// Draw the smoke over `leds`, keeping whichever is brighter of the smoke and what's already there.
+    pub fn draw(&self, leds: &mut [LED], micros: u64) {
+        let smoke_color = RGB { r: 140, g: 140, b: 150 }; // config: the color of the smoke at its thickest. it's dim, it's only smoke
+        let source_height = 0.5; // config: where the smoke starts, as a fraction of the layout height. it only rises through the upper LEDs
+        let rise_speed = 0.2; // config: how fast the smoke rises, in layout heights per second
+        let trail_secs = 2.0; // config: how long a trail the smoke leaves behind as it rises, in seconds of rising
+        let sway = 0.25; // config: how far the wisp curls side to side at the top, as a fraction of the layout width
+
+        let Some(puffed_at) = self.puffed_at else { return };
+        let secs = micros.saturating_sub(puffed_at) as f32 / 1_000_000.0;
+        if secs >= Self::DURATION_SECS {
+            return;
+        }
+        let fade = (1.0 - secs / Self::DURATION_SECS).powi(2); // thins out quickly at first, then lingers
+        let height = self.bounds.height();
+        let source_y = self.bounds.min_y + source_height * height;
+        let head_y = source_y + rise_speed * height * secs; // the front of the rising smoke
+        let tail_y = source_y + rise_speed * height * (secs - trail_secs).max(0.0); // where the trail has already cleared
+        let (center_x, _) = self.bounds.center();
+
+        for led in leds.iter_mut() {
+            let x = led.coords.0 as f32;
+            let y = led.coords.1 as f32;
+            if y < tail_y || y > head_y + 2.0 {
+                continue;
+            }
+            // the higher the smoke gets above where it started, the more it curls and spreads out
+            let rise_pct = ((y - source_y) / (self.bounds.max_y - source_y)).max(0.0);
+            let curl = perlin1(y * 0.08 - secs * 0.6, 7.0) * sway * self.bounds.width() * rise_pct * 1.5;
+            let width = 2.5 + 5.0 * rise_pct;
+            let dx = x - (center_x + curl);
+            let across = f32::exp(-dx * dx / (2.0 * width * width));
+            let front = ((head_y + 2.0 - y) / 4.0).clamp(0.0, 1.0); // soft leading edge
+            let density = across * front * fade;
+
+            let smoke = scale(smoke_color, density);
+            led.color = RGB { r: led.color.r.max(smoke.r), g: led.color.g.max(smoke.g), b: led.color.b.max(smoke.b) };
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct SmokeWispSim {
+    wisp: SmokeWisp,
+}
+
+impl Simulation for SmokeWispSim {
+    fn new(leds: &[LED]) -> Self where Self: Sized {
+        Self { wisp: SmokeWisp::new(leds) }
+    }
+
+    fn get_name(&self) -> &str {
+        "Smoke wisp"
+    }
+
+    fn lit_by_power_on(&self) -> bool {
+        false // only smoke, there's nothing to light
+    }
+
+    fn tick(
+        &mut self,
+        leds: &mut Vec<LED>,
+        micros: u64,
+        _intensity_mod: f32,
+    ) {
+        let pause_secs = 1.5; // config: how long to wait after one wisp fades before puffing the next
+
+        let due = self.wisp.puffed_at.is_none_or(|puffed_at| {
+            micros.saturating_sub(puffed_at) as f32 / 1_000_000.0 >= SmokeWisp::DURATION_SECS + pause_secs
+        });
+        if due {
+            self.wisp.puff(micros);
+        }
+        for led in leds.iter_mut() {
+            led.color = RGB { r: 0, g: 0, b: 0 };
+        }
+        self.wisp.draw(leds, micros);
+    }
+}
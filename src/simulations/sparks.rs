@@ -0,0 +1,117 @@
+use rand::Rng;
+
+use crate::{
+    color::hsv_to_rgb,
+    layout::Bounds,
+    noise::perlin1,
+    types::{frame_dt, Simulation, LED, RGB},
+};
+
+// embers spawn near the bottom of the layout, float upward with a bit of noisy sway, and cool
+// from white-yellow to dull red over their lifetime. each ember lights up the LEDs near it, so it
+// looks like a spark passing by even though it's between LEDs most of the time.
+// the background is left black, so this is meant to be layered on top of another flame as well as run alone.
+
+#[derive(Debug)]
+struct Ember {
+    x: f32,
+    y: f32,
+    rise_speed: f32, // layout units per second
+    age: f32, // seconds
+    lifetime: f32, // seconds
+    sway_channel: f32, // which noise stream this ember sways along, so they don't all sway together
+}
+
+#[derive(Debug)]
+pub struct SparksSim {
+    last_tick: u64,
+    embers: Vec<Ember>,
+    bounds: Bounds,
+}
+
+impl SparksSim {
+    /// Ember color from its temperature, 1 (just spawned) to 0 (burnt out).
+    fn ember_color(temperature: f32) -> [f32; 3] {
+        let hue = 10.0 + 40.0 * temperature; // red when cool, yellow when hot
+        let sat = 1.0 - 0.5 * temperature.powi(3); // very hot embers are nearly white
+        let color = hsv_to_rgb(hue, sat, temperature.sqrt());
+        [f32::from(color.r), f32::from(color.g), f32::from(color.b)]
+    }
+
+    fn spawn(&mut self, rng: &mut impl Rng) {
+        let (center_x, _) = self.bounds.center();
+        let spawn_width = self.bounds.width() * 0.3; // config: how wide an area embers spawn from
+        self.embers.push(Ember {
+            x: center_x + rng.gen_range(-0.5..0.5) * spawn_width,
+            y: self.bounds.min_y + rng.gen_range(0.0..4.0),
+            rise_speed: rng.gen_range(8.0..18.0),
+            age: 0.0,
+            lifetime: rng.gen_range(1.0..2.5),
+            sway_channel: rng.gen_range(0.0..100.0),
+        });
+    }
+}
+
+impl Simulation for SparksSim {
+    fn new(leds: &[LED]) -> Self where Self: Sized {
+        Self { last_tick: 0, embers: vec![], bounds: Bounds::of(leds) }
+    }
+
+    fn get_name(&self) -> &str {
+        "Rising sparks"
+    }
+
+    fn tick(
+        &mut self,
+        leds: &mut Vec<LED>,
+        micros: u64,
+        intensity_mod: f32,
+    ) {
+        let spawn_rate = 0.5 + 3.5 * intensity_mod; // config: embers per second
+        let sway = 6.0; // config: how far embers drift side to side, in layout units per second
+        let drag = 0.4; // config: how quickly embers lose their upward speed, per second
+        let glow_radius = 2.5; // config: how far an ember's light reaches, in layout units
+
+        let dt = frame_dt(self.last_tick, micros, 0.1);
+        self.last_tick = micros;
+        let mut rng = rand::thread_rng();
+
+        // spawn new embers. at high frame rates spawn_rate * dt is well below 1, so this is a coin flip
+        let mut expected_spawns = spawn_rate * dt;
+        while expected_spawns > 0.0 {
+            if rng.gen::<f32>() < expected_spawns.min(1.0) {
+                self.spawn(&mut rng);
+            }
+            expected_spawns -= 1.0;
+        }
+
+        // move and age the embers, and get rid of the ones that burnt out or left the top
+        for ember in &mut self.embers {
+            ember.age += dt;
+            ember.rise_speed *= 1.0 - drag * dt;
+            ember.y += ember.rise_speed * dt;
+            ember.x += perlin1(ember.age * 1.5, ember.sway_channel) * sway * dt;
+        }
+        let top = self.bounds.max_y + glow_radius * 2.0;
+        self.embers.retain(|ember| ember.age < ember.lifetime && ember.y < top);
+
+        // splat each ember's light onto the LEDs around it
+        for led in leds.iter_mut() {
+            let mut light = [0.0f32; 3];
+            for ember in &self.embers {
+                let dx = led.coords.0 as f32 - ember.x;
+                let dy = led.coords.1 as f32 - ember.y;
+                let falloff = f32::exp(-(dx * dx + dy * dy) / (2.0 * glow_radius * glow_radius));
+                let color = Self::ember_color(1.0 - ember.age / ember.lifetime);
+                for (channel, value) in light.iter_mut().zip(color) {
+                    *channel += value * falloff;
+                }
+            }
+            led.color = RGB {
+                r: light[0].min(255.0) as u8,
+                g: light[1].min(255.0) as u8,
+                b: light[2].min(255.0) as u8,
+            };
+        }
+    }
+}
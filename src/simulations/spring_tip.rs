@@ -0,0 +1,120 @@
+use crate::{
+    color::heat_color,
+    layout::Bounds,
+    noise::perlin1,
+    types::{frame_dt, Simulation, LED},
+};
+
+// the tip of the flame is a mass on a spring, pulled back toward where it would rest in still air
+// and pushed around by noisy "turbulence" forces. because it has momentum it overshoots and sways back
+// instead of jumping straight to wherever the noise says, which is how a real flame tip moves.
+// the flame is drawn as a teardrop from the middle of the wick up to the tip, bending toward the tip near the top.
+
+const SUBSTEP_SECS: f32 = 1.0 / 240.0; // the spring is integrated in small fixed steps so it stays stable at any frame rate
+
+#[derive(Debug)]
+pub struct SpringTipSim {
+    last_tick: u64,
+    time: f32, // seconds simulated so far, drives the noise
+    unsimulated_secs: f32, // time that hasn't been simulated yet because it's less than a substep
+    tip: (f32, f32), // offset of the tip from where it rests, in layout units
+    velocity: (f32, f32), // layout units per second
+    stiffness: f32, // how hard the spring pulls the tip back, per second²
+    damping: f32, // how quickly the swaying dies down, per second
+    bounds: Bounds,
+}
+
+impl SpringTipSim {
+    const PRESETS: [&'static str; 3] = ["Default", "Bouncy", "Heavy"];
+
+    fn substep(&mut self, dt: f32, intensity_mod: f32) {
+        let turbulence = 150.0 + 450.0 * intensity_mod; // config: how hard the air pushes the tip around, layout units/s²
+        let gustiness = 1.2 + 1.5 * intensity_mod; // config: how quickly the turbulence changes, in noise units per second
+
+        self.time += dt;
+        // the tip is pushed up and down harder than side to side, like a flame stretching and shrinking
+        let force_x = perlin1(self.time * gustiness, 1.0) * turbulence * 0.6;
+        let force_y = perlin1(self.time * gustiness, 2.0) * turbulence;
+
+        // a damped spring: F = -k·x - c·v, with the mass taken as 1
+        let accel_x = force_x - self.stiffness * self.tip.0 - self.damping * self.velocity.0;
+        let accel_y = force_y - self.stiffness * self.tip.1 - self.damping * self.velocity.1;
+        // semi-implicit Euler: update the velocity first and move with the new one, which doesn't gain energy
+        self.velocity.0 += accel_x * dt;
+        self.velocity.1 += accel_y * dt;
+        self.tip.0 += self.velocity.0 * dt;
+        self.tip.1 += self.velocity.1 * dt;
+    }
+}
+
+impl Simulation for SpringTipSim {
+    fn new(leds: &[LED]) -> Self where Self: Sized {
+        Self {
+            last_tick: 0,
+            time: 0.0,
+            unsimulated_secs: 0.0,
+            tip: (0.0, 0.0),
+            velocity: (0.0, 0.0),
+            stiffness: 30.0,
+            damping: 3.0,
+            bounds: Bounds::of(leds),
+        }
+    }
+
+    fn get_name(&self) -> &str {
+        "Spring-damper tip"
+    }
+
+    fn get_presets(&self) -> &'static [&'static str] {
+        &Self::PRESETS
+    }
+
+    fn apply_preset(&mut self, preset: &str) -> bool {
+        (self.stiffness, self.damping) = match preset {
+            "Default" => (30.0, 3.0),
+            "Bouncy" => (60.0, 1.0), // quick, springy wobbles that take a while to settle
+            "Heavy" => (12.0, 5.0), // slow, lazy sways with hardly any overshoot
+            _ => return false,
+        };
+        true
+    }
+
+    fn tick(
+        &mut self,
+        leds: &mut Vec<LED>,
+        micros: u64,
+        intensity_mod: f32,
+    ) {
+        let rest_height = 0.55 + 0.35 * intensity_mod; // config: where the tip sits in still air, as a fraction of the layout height
+        let base_width = 1.3; // config: how wide the flame is at the wick, as a fraction of the layout width
+        let edge_softness = 0.5; // config: how gradually the flame fades out at its edges
+
+        let dt = frame_dt(self.last_tick, micros, 0.1);
+        self.last_tick = micros;
+        self.unsimulated_secs += dt;
+        while self.unsimulated_secs >= SUBSTEP_SECS {
+            self.substep(SUBSTEP_SECS, intensity_mod);
+            self.unsimulated_secs -= SUBSTEP_SECS;
+        }
+
+        let (center_x, _) = self.bounds.center();
+        let base = (center_x, self.bounds.min_y);
+        let tip_x = center_x + self.tip.0;
+        let tip_y = (self.bounds.min_y + rest_height * self.bounds.height() + self.tip.1).max(self.bounds.min_y + 1.0);
+        let half_width = base_width * self.bounds.width() / 2.0;
+
+        for led in leds.iter_mut() {
+            let x = led.coords.0 as f32;
+            let y = led.coords.1 as f32;
+            let along = ((y - base.1) / (tip_y - base.1)).max(0.0); // 0 at the wick, 1 at the tip
+            // the flame's middle line bends over to the tip near the top, while the bottom stays on the wick
+            let axis_x = base.0 + (tip_x - base.0) * along.powi(2);
+            // widest at the wick, pinching to a point at the tip
+            let width = half_width * (1.0 - along).max(0.0).sqrt() * (0.6 + 0.4 * (1.0 - along));
+            let side = if width > 0.0 { (x - axis_x).abs() / width } else { f32::MAX };
+            let inside = (1.0 - side) / edge_softness + 0.5; // above 1 well inside the flame, below 0 outside it
+            let heat = inside.clamp(0.0, 1.0) * (1.0 - 0.5 * along.min(1.0)); // cooler toward the tip
+            led.color = heat_color(heat);
+        }
+    }
+}
@@ -0,0 +1,78 @@
+use crate::{
+    color::heat_color,
+    layout::Bounds,
+    noise::perlin1,
+    types::{Simulation, LED},
+};
+
+// a torch outdoors: the wind is a vector that wanders around, with a steady breeze plus gusts on top.
+// the flame leans and stretches the way the wind blows, the LEDs on the downwind side get hotter,
+// and a strong gust flattens the flame so it's shorter. a higher intensity means a windier night.
+
+#[derive(Debug)]
+pub struct TorchWindSim {
+    bounds: Bounds,
+}
+
+impl TorchWindSim {
+    /// The wind at time `t` in seconds, as (sideways, upward), in units of "strong enough to flatten the flame".
+    fn wind(t: f32, strength: f32) -> (f32, f32) {
+        let breeze_speed = 0.08; // config: how fast the steady breeze changes direction, in noise units per second
+        let gust_speed = 0.9; // config: how fast gusts come and go, in noise units per second
+
+        // the breeze drifts slowly from side to side
+        let breeze = perlin1(t * breeze_speed, 0.0) * 1.6;
+        // gusts are short bursts in the direction the breeze is already blowing
+        let gust = (perlin1(t * gust_speed, 1.0) * 2.0 - 0.2).max(0.0) * breeze.signum();
+        // eddies push the flame up and down a little
+        let updraft = perlin1(t * gust_speed * 1.3, 2.0) * 0.3;
+        ((breeze + gust) * strength, updraft * strength)
+    }
+}
+
+impl Simulation for TorchWindSim {
+    fn new(leds: &[LED]) -> Self where Self: Sized {
+        Self { bounds: Bounds::of(leds) }
+    }
+
+    fn get_name(&self) -> &str {
+        "Torch in the wind"
+    }
+
+    fn tick(
+        &mut self,
+        leds: &mut Vec<LED>,
+        micros: u64,
+        intensity_mod: f32,
+    ) {
+        let wind_strength = 0.2 + 0.8 * intensity_mod; // config: how windy it is
+        let still_height = 1.1; // config: how tall the flame is with no wind, as a fraction of the layout height
+        let max_lean = 0.7; // config: how far sideways the tip can be blown, as a fraction of the layout width
+        let downwind_boost = 0.5; // config: how much hotter the downwind side gets in a full-strength wind
+
+        let t = micros as f32 / 1_000_000.0;
+        let (wind_x, wind_y) = Self::wind(t, wind_strength);
+        let speed = wind_x.hypot(wind_y).min(1.5);
+
+        // strong winds flatten the flame, updrafts stretch it
+        let height = (still_height * (1.0 - 0.35 * speed) + wind_y * 0.3).max(0.2) * self.bounds.height();
+        let lean = wind_x.clamp(-1.0, 1.0) * max_lean * self.bounds.width();
+        let (center_x, _) = self.bounds.center();
+        let half_width = self.bounds.width() * 0.6;
+
+        for led in leds.iter_mut() {
+            let x = led.coords.0 as f32;
+            let y = led.coords.1 as f32;
+            let height_pct = ((y - self.bounds.min_y) / height).max(0.0); // 0 at the bottom, 1 at the top of the flame
+            // the flame bends more the further up it goes
+            let axis_x = center_x + lean * height_pct.min(1.0).powf(1.5);
+            // blown flames get stretched out along the wind, so they're a bit wider at the top
+            let width = half_width * (1.0 - (0.6 - 0.3 * speed.min(1.0)) * height_pct.min(1.0));
+            let side_pct = (x - axis_x).abs() / width;
+            // LEDs the wind is blowing toward catch more of the flame, and the ones upwind catch less
+            let downwind = ((x - center_x) / (self.bounds.width() / 2.0) * wind_x.clamp(-1.0, 1.0)).clamp(-1.0, 1.0);
+            let heat = (1.0 - height_pct).min(1.0 - side_pct.powi(2)) * 1.4 * (1.0 + downwind_boost * downwind);
+            led.color = heat_color(heat);
+        }
+    }
+}
@@ -0,0 +1,161 @@
+//! Smooth hand-offs between two simulations. Both simulations keep ticking into their own buffers
+//! while the transition runs, and the two buffers are mixed into the LEDs that actually get shown.
+use std::time::Duration;
+
+use crate::{color::{mix, scale}, layout::blank_leds, types::{Simulation, LED}};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TransitionStyle {
+    /// blend every LED from the old color to the new one at the same time
+    Crossfade,
+    /// the new simulation rises up from the lowest LEDs like a liquid filling a glass
+    WipeFromBottom,
+    /// fade the old simulation out to black, then fade the new one in
+    DipToBlack,
+}
+
+impl TransitionStyle {
+    pub const ALL: [Self; 3] = [Self::Crossfade, Self::WipeFromBottom, Self::DipToBlack];
+
+    #[must_use] pub const fn get_name(self) -> &'static str {
+        match self {
+            Self::Crossfade => "Crossfade",
+            Self::WipeFromBottom => "Wipe from bottom",
+            Self::DipToBlack => "Dip to black",
+        }
+    }
+
+    /// The style after this one, wrapping around. Used to cycle through styles from the UI.
+    #[must_use] pub fn next(self) -> Self {
+        let idx = Self::ALL.iter().position(|style| *style == self).unwrap_or(0);
+        Self::ALL[(idx + 1) % Self::ALL.len()]
+    }
+}
+
+#[derive(Debug)]
+pub struct Transition {
+    pub from: usize, // index of the simulation we're leaving
+    pub to: usize, // index of the simulation we're going to
+    style: TransitionStyle,
+    stop_micros: u64, // when the outgoing simulation was stopped
+    start_micros: Option<u64>, // when the blend started. None while the outgoing simulation is still playing its ending
+    duration_micros: u64,
+    from_leds: Vec<LED>,
+    to_leds: Vec<LED>,
+    // the y range of the layout, for the wipe
+    min_y: f32,
+    max_y: f32,
+}
+
+impl Transition {
+    /// Start a transition at `micros`. `current_leds` is the buffer the outgoing simulation has been drawing into,
+    /// so simulations that rely on their previous frame (like `FlashEverySecondSim`) keep working.
+    /// The outgoing simulation is stopped, and any ending it has (like a burnout) plays out in full before the blend starts.
+    /// The incoming simulation is started once the outgoing one reports it has stopped.
+    pub fn new(
+        simulations: &mut [Box<dyn Simulation>],
+        from: usize,
+        to: usize,
+        style: TransitionStyle,
+        duration: Duration,
+        micros: u64,
+        current_leds: &[LED],
+    ) -> Self {
+        simulations[from].stop(micros);
+        let from_leds = current_leds.to_vec();
+        let to_leds = blank_leds(current_leds);
+        let min_y = current_leds.iter().map(|led| led.coords.1).min().unwrap_or(0) as f32;
+        let max_y = current_leds.iter().map(|led| led.coords.1).max().unwrap_or(0) as f32;
+        Self {
+            from,
+            to,
+            style,
+            stop_micros: micros,
+            start_micros: None,
+            duration_micros: duration.as_micros().try_into().unwrap_or(u64::MAX),
+            from_leds,
+            to_leds,
+            min_y,
+            max_y,
+        }
+    }
+
+    #[must_use] pub const fn style(&self) -> TransitionStyle {
+        self.style
+    }
+
+    /// When the blend started, or None while the outgoing simulation is still playing its ending.
+    #[must_use] pub const fn started_at(&self) -> Option<u64> {
+        self.start_micros
+    }
+
+    /// How far along the transition is, from 0 to 1. Stays at 0 while the outgoing simulation plays its ending.
+    #[must_use] pub fn progress(&self, micros: u64) -> f32 {
+        let Some(start_micros) = self.start_micros else {
+            return 0.0;
+        };
+        if self.duration_micros == 0 {
+            return 1.0;
+        }
+        let elapsed = micros.saturating_sub(start_micros);
+        (elapsed as f32 / self.duration_micros as f32).min(1.0)
+    }
+
+    #[must_use] pub fn is_finished(&self, micros: u64) -> bool {
+        self.progress(micros) >= 1.0
+    }
+
+    /// Tick both simulations and write the mixed result into `leds`.
+    pub fn tick(
+        &mut self,
+        simulations: &mut [Box<dyn Simulation>],
+        leds: &mut [LED],
+        micros: u64,
+        intensity_mod: f32,
+    ) {
+        let max_ending_micros = 15_000_000; // config: the longest we wait for the outgoing simulation's ending
+
+        simulations[self.from].tick(&mut self.from_leds, micros, intensity_mod);
+        if self.start_micros.is_none() {
+            let ending_too_long = micros.saturating_sub(self.stop_micros) >= max_ending_micros;
+            if !simulations[self.from].is_stopped() && !ending_too_long {
+                // still ending: show it as it is
+                for (led, from) in leds.iter_mut().zip(&self.from_leds) {
+                    led.color = from.color;
+                }
+                return;
+            }
+            simulations[self.to].start(micros);
+            self.start_micros = Some(micros);
+        }
+        simulations[self.to].tick(&mut self.to_leds, micros, intensity_mod);
+
+        let progress = self.progress(micros);
+        for (i, led) in leds.iter_mut().enumerate() {
+            let from = self.from_leds[i].color;
+            let to = self.to_leds[i].color;
+            led.color = match self.style {
+                TransitionStyle::Crossfade => mix(from, to, progress),
+                TransitionStyle::WipeFromBottom => {
+                    // the edge of the wipe is a few units tall so LEDs fade through instead of popping
+                    let edge_width = 4.0; // config: how soft the edge of the wipe is
+                    let edge = progress * (self.max_y - self.min_y + 2.0 * edge_width) + self.min_y - edge_width;
+                    let amount = ((edge - self.from_leds[i].coords.1 as f32) / edge_width + 0.5).clamp(0.0, 1.0);
+                    mix(from, to, amount)
+                }
+                TransitionStyle::DipToBlack => {
+                    if progress < 0.5 {
+                        scale(from, 1.0 - progress * 2.0)
+                    } else {
+                        scale(to, progress * 2.0 - 1.0)
+                    }
+                }
+            };
+        }
+    }
+
+    /// Hand over the incoming simulation's buffer once the transition is done, so it can keep drawing on its own.
+    #[must_use] pub fn into_leds(self) -> Vec<LED> {
+        self.to_leds
+    }
+}
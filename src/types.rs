@@ -7,10 +7,24 @@ pub struct RGB<ComponentType> {
     pub b: ComponentType,
 }
 
-#[derive(Debug)]
+/// What part of the candle an LED sits in on the board, for simulations that color parts differently.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Role {
+    /// the bottom edge of the flame, where it burns blue
+    Base,
+    /// the dark wick in the middle of the flame
+    Wick,
+    /// the body of the flame
+    Flame,
+    /// the outline of the flame, around the body
+    Outer,
+}
+
+#[derive(Clone, Debug)]
 pub struct LED {
     pub color: RGB<u8>,
     pub coords: (usize, usize),
+    pub role: Option<Role>, // None if the layout doesn't say
 }
 
 pub trait Simulation: Debug {
@@ -23,5 +37,44 @@ pub trait Simulation: Debug {
 
     fn new(leds: &[LED]) -> Self where Self: Sized;
 
-    fn get_name(&self) -> &'static str;
-}
\ No newline at end of file
+    fn get_name(&self) -> &str;
+
+    /// Names of the presets this simulation can switch between, if any. Presets are picked from the
+    /// simulation page or by playlist entries.
+    fn get_presets(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    /// Switch to one of the presets from `get_presets`. Returns false if the preset doesn't exist.
+    fn apply_preset(&mut self, _preset: &str) -> bool {
+        false
+    }
+
+    /// A line of extra information to show under the simulation, if it has any.
+    fn get_status(&self) -> Option<String> {
+        None
+    }
+
+    /// Called when the simulation starts being shown, with the time it starts at.
+    fn start(&mut self, _micros: u64) {}
+
+    /// Called when the simulation is about to stop being shown. Simulations with an ending start playing it here.
+    fn stop(&mut self, _micros: u64) {}
+
+    /// Whether the ending started by `stop` has finished. The simulation keeps getting ticked until it has.
+    fn is_stopped(&self) -> bool {
+        true
+    }
+
+    /// Whether the match struck when the app starts should light this simulation.
+    /// Simulations that aren't flames, or that light themselves, say no.
+    fn lit_by_power_on(&self) -> bool {
+        true
+    }
+}
+
+/// Seconds between the last tick at `last_tick` and this one at `micros`, capped at `max_secs` so that a pause
+/// (or the first tick, when `last_tick` is 0) doesn't make a simulation jump ahead.
+#[must_use] pub fn frame_dt(last_tick: u64, micros: u64, max_secs: f32) -> f32 {
+    (micros.saturating_sub(last_tick) as f32 / 1_000_000.0).min(max_secs)
+}